@@ -0,0 +1,170 @@
+use eframe::egui::{self, Button, Color32, Frame, ScrollArea, Ui, Widget, Window};
+use crate::medit::{Command, IconName, MarkDownImpl};
+use crate::space::NoteSpace;
+use crate::ToolBar;
+
+#[derive(Clone, Debug)]
+pub struct BacklinkItem {
+    pub parent: String,
+    pub line_no: usize,     //line_no in editor
+    pub line_text: String,
+}
+
+/// notes which link to the current note
+pub struct BacklinksPanel {
+    pub is_window: bool,
+    note: Option<String>,
+    data_version: usize,
+    items: Vec<BacklinkItem>,
+}
+
+impl BacklinksPanel {
+    pub fn new() -> Self {
+        Self {
+            is_window: false,
+            note: None,
+            data_version: 0,
+            items: vec![],
+        }
+    }
+
+    /// lines of parents with links to the note, alias, anchor and embed included
+    pub fn find_backlinks(note_space: &NoteSpace, name: &str) -> Vec<BacklinkItem> {
+        let targets = note_space.link_targets(name);
+        let mut items = vec![];
+        for parent in note_space.get_parents(name) {
+            let Ok(text) = note_space.read_note(&parent) else {
+                continue;
+            };
+            let editor_lines = NoteSpace::text_to_lines(&text);
+            let mut line_nos = vec![];
+            for link in MarkDownImpl::wiki_links(&text).iter().filter(|link| targets.contains(&link.target)) {
+                if let Some(line_no) = NoteSpace::offset_to_line_no(&text, link.range.start) {
+                    if line_no < editor_lines.len() && !line_nos.contains(&line_no) {
+                        line_nos.push(line_no);
+                    }
+                }
+            }
+            for line_no in line_nos {
+                items.push(BacklinkItem {
+                    parent: parent.clone(),
+                    line_no,
+                    line_text: editor_lines[line_no].clone(),
+                });
+            }
+        }
+        items
+    }
+
+    /// rebuild items when current note or the links have changed
+    pub fn flash(&mut self, note_space: &NoteSpace, force: bool) {
+        let note = note_space.get_current_note();
+        if !force && note == self.note && note_space.data_version() == self.data_version {
+            return;
+        }
+        self.items = match &note {
+            Some(name) => Self::find_backlinks(note_space, name),
+            None => vec![],
+        };
+        self.note = note;
+        self.data_version = note_space.data_version();
+    }
+
+    fn open_line_cmds(item: &BacklinkItem) -> Vec<Command> {
+        vec![
+            Command::OpenFile(item.parent.clone()),
+            Command::ClickEditLine(format!("{} {}", item.line_no + 1, item.line_text)),
+        ]
+    }
+
+    fn show_content(&mut self, ui: &mut Ui, is_show: &mut bool) -> Vec<Command> {
+        let mut cmds = vec![];
+        ui.horizontal(|ui| {
+            if ToolBar::tool_icon_button(ui, IconName::icon_close, false, false, "Close").clicked() {
+                *is_show = false;
+            }
+            ui.label(format!("Backlinks: {} items", self.items.len()));
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let dock_text = if self.is_window {"dock"} else {"float"};
+                if ui.small_button(dock_text).clicked() {
+                    self.is_window = !self.is_window;
+                }
+                if ToolBar::tool_icon_button(ui, IconName::icon_refresh, false, false, "Refresh").clicked() {
+                    self.data_version = 0;
+                }
+            });
+        });
+        ui.separator();
+
+        ScrollArea::both().auto_shrink(false).show(ui, |ui| {
+            let mut pre_parent = String::new();
+            for item in &self.items {
+                //group by parent note
+                if item.parent != pre_parent {
+                    pre_parent = item.parent.clone();
+                    ui.add_space(4.0);
+                    if ui.add(Button::new(egui::RichText::new(&item.parent).strong()).fill(Color32::TRANSPARENT)).clicked() {
+                        cmds.push(Command::OpenFile(item.parent.clone()));
+                    }
+                }
+                let text = format!("{:>4} {}", item.line_no + 1, item.line_text.trim());
+                let r = Button::new(text).fill(Color32::TRANSPARENT).wrap().ui(ui);
+                if r.clicked() {
+                    cmds = Self::open_line_cmds(item);
+                }
+            }
+            if self.items.is_empty() {
+                ui.weak("No backlinks");
+            }
+        });
+        cmds
+    }
+
+    /// show as right side panel or a window
+    pub fn show(&mut self, ctx: &egui::Context, note_space: &NoteSpace, is_show: &mut bool) -> Vec<Command> {
+        let mut cmds = vec![];
+        if !*is_show {
+            return cmds;
+        }
+        self.flash(note_space, false);
+
+        if self.is_window {
+            let mut open = true;
+            Window::new("backlinks")
+                .default_size([320.0, 360.0])
+                .title_bar(false)
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    cmds = self.show_content(ui, is_show);
+                });
+        } else {
+            egui::SidePanel::right("backlinks")
+                .resizable(true)
+                .default_width(260.0)
+                .frame(Frame::side_top_panel(&ctx.style()))
+                .show(ctx, |ui| {
+                    cmds = self.show_content(ui, is_show);
+                });
+        }
+        cmds
+    }
+}
+
+#[test]
+pub fn test_find_backlinks() {
+    let dir = std::env::temp_dir().join(format!("egscribe_backlinks_{}", std::process::id()));
+    let _ = std::fs::create_dir_all(&dir);
+    std::fs::write(dir.join("n.md"), "---\nid: 0190-n\n---\n# N\n").unwrap();
+    std::fs::write(dir.join("p.md"), "see [[n|alias]]\n\n[[n#Sec]] and ![[n]]\n\n`[[n]]`\n\n[[id:0190-n]]\n").unwrap();
+    //the same lines, and a line in other one
+    std::fs::write(dir.join("q.md"), "[[n]]\n\n[[n]]\n\nsee [[n]] and [[n]] again\n").unwrap();
+
+    let space = NoteSpace::new(dir.clone());
+    let items = BacklinksPanel::find_backlinks(&space, "n");
+    let lines = |parent: &str| -> Vec<(usize, String)> {
+        items.iter().filter(|i| i.parent == parent).map(|i| (i.line_no, i.line_text.clone())).collect()
+    };
+    assert_eq!(lines("p"), vec![(0, "see [[n|alias]]".into()), (1, "[[n#Sec]] and ![[n]]".into()), (3, "[[id:0190-n]]".into())]);
+    assert_eq!(lines("q"), vec![(0, "[[n]]".into()), (1, "[[n]]".into()), (2, "see [[n]] and [[n]] again".into())]);
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,355 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use eframe::egui::{Order, TextEdit, Ui, Window};
+use regex::Regex;
+use serde::Serialize;
+use crate::medit::{FrontMatter, MarkDownImpl};
+use crate::space::{DirNote, NoteSpace};
+use crate::import::is_image;
+
+const STYLE_FILE: &str = "style.css";
+const SEARCH_FILE: &str = "search-index.js";
+/// text of each note in the search index is cut to this length
+const SEARCH_TEXT_LEN: usize = 5000;
+
+const STYLE: &str = r#"body { margin: 0; display: flex; font-family: sans-serif; line-height: 1.6; color: #222; }
+nav { width: 260px; min-height: 100vh; padding: 12px; box-sizing: border-box; background: #f5f5f5; border-right: 1px solid #ddd; }
+nav ul { list-style: none; padding: 0; margin: 0; }
+nav li a { display: block; padding: 2px 0; color: #333; text-decoration: none; white-space: nowrap; overflow: hidden; text-overflow: ellipsis; }
+nav li a.current { font-weight: bold; }
+#search { width: 100%; box-sizing: border-box; margin-bottom: 8px; padding: 4px; }
+#results a { display: block; padding: 2px 0; }
+main { flex: 1; padding: 16px 32px; max-width: 860px; }
+img { max-width: 100%; }
+pre { background: #f5f5f5; padding: 8px; overflow: auto; }
+"#;
+
+const SEARCH_SCRIPT: &str = r#"(function() {
+  var root = document.body.dataset.root;
+  var input = document.getElementById('search');
+  var results = document.getElementById('results');
+  input.addEventListener('input', function() {
+    var q = input.value.trim().toLowerCase();
+    results.innerHTML = '';
+    if (!q) { return; }
+    SEARCH_INDEX.filter(function(p) {
+      return p.title.toLowerCase().indexOf(q) >= 0 || p.text.toLowerCase().indexOf(q) >= 0;
+    }).slice(0, 50).forEach(function(p) {
+      var a = document.createElement('a');
+      a.href = root + p.url;
+      a.textContent = p.title;
+      results.appendChild(a);
+    });
+  });
+})();"#;
+
+#[derive(Serialize)]
+struct SearchItem {
+    title: String,
+    url: String,        //relative to the site dir
+    text: String,
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn url_escape(s: &str) -> String {
+    s.replace('%', "%25").replace(' ', "%20").replace('?', "%3F").replace('"', "%22")
+}
+
+/// id of heading, also used in "[[note#heading]]" links
+fn slug(text: &str) -> String {
+    let mut s = String::new();
+    for c in text.trim().to_lowercase().chars() {
+        if c.is_alphanumeric() || c == '_' {
+            s.push(c);
+        } else if (c.is_whitespace() || c == '-') && !s.ends_with('-') {
+            s.push('-');
+        }
+    }
+    s.trim_matches('-').to_string()
+}
+
+/// "../" for each folder of the note, the page is in the same folders of site dir
+fn root_prefix(name: &str) -> String {
+    "../".repeat(name.matches('/').count())
+}
+
+fn page_url(name: &str) -> String {
+    url_escape(&format!("{}.html", name))
+}
+
+/// [[links]] to exported notes become markdown links relative to the page, others become the text
+/// "![[p.png]]" becomes a markdown image of the images dir, the src is fixed in fix_html
+/// resolve gets the note name of link target, for "[[id:...]]"
+pub fn rewrite_wiki_links(text: &str, name: &str, notes: &HashSet<String>, resolve: impl Fn(&str) -> String) -> String {
+    let prefix = root_prefix(name);
+    let mut new_text = text.to_string();
+    for link in MarkDownImpl::wiki_links(text).iter().rev() {
+        if link.embed && is_image(Path::new(&link.target)) {
+            let file = link.target.rsplit(['/', '\\']).next().unwrap_or(&link.target);
+            let image = format!("![{}](<{}>)", link.alias.as_deref().unwrap_or_default(), url_escape(file));
+            new_text.replace_range(link.range.clone(), &image);
+            continue;
+        }
+        let target = resolve(&link.target);
+        let show = link.alias.clone()
+            .or_else(|| link.anchor.as_ref().map(|a| format!("{} > {}", target, a)))
+            .unwrap_or_else(|| target.clone());
+        let replace = if notes.contains(&target) {
+            let mut href = format!("{}{}", prefix, page_url(&target));
+            if let Some(anchor) = &link.anchor {
+                href += &format!("#{}", url_escape(&slug(anchor)));
+            }
+            format!("[{}](<{}>)", show, href)
+        } else {
+            show
+        };
+        new_text.replace_range(link.range.clone(), &replace);
+    }
+    new_text
+}
+
+/// ids of headings and paths of local images
+fn fix_html(html: &str, prefix: &str) -> String {
+    let re_tag = Regex::new(r"<[^>]*>").unwrap();
+    let re_heading = Regex::new(r"(?s)<h([1-6])>(.*?)</h([1-6])>").unwrap();
+    let html = re_heading.replace_all(html, |cap: &regex::Captures| {
+        let id = slug(&re_tag.replace_all(&cap[2], ""));
+        format!("<h{} id=\"{}\">{}</h{}>", &cap[1], id, &cap[2], &cap[3])
+    });
+    //images are saved in "images" of work dir, the url is relative to it
+    let re_img = Regex::new(r#"<img src="([^"]*)""#).unwrap();
+    re_img.replace_all(&html, |cap: &regex::Captures| {
+        let src = &cap[1];
+        if src.contains("://") || src.starts_with('/') || src.starts_with("data:") {
+            cap[0].to_string()
+        } else {
+            format!("<img src=\"{}images/{}\"", prefix, src)
+        }
+    }).to_string()
+}
+
+/// absolute path of a dir which may not exist yet
+fn abs_dir(path: &Path) -> std::io::Result<PathBuf> {
+    let mut existing = path;
+    let mut rest = vec![];
+    while !existing.exists() {
+        let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+            break;
+        };
+        rest.push(name);
+        //"out" is in the current dir
+        existing = if parent.as_os_str().is_empty() {Path::new(".")} else {parent};
+    }
+    let mut abs = std::fs::canonicalize(existing)?;
+    abs.extend(rest.iter().rev());
+    Ok(abs)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            copy_dir(&path, &to.join(entry.file_name()))?;
+        } else {
+            std::fs::copy(&path, to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// notes under root in the link tree rendered as html pages
+pub struct SiteExport {
+    tree: Vec<DirNote>,
+    notes: HashSet<String>,
+}
+
+impl SiteExport {
+    pub fn new(note_space: &NoteSpace, root: &str) -> Self {
+        //a page for each note, back-references to ancestors are left out
+        //dangling links have no page, they are written as plain text
+        let tree: Vec<DirNote> = note_space.link_tree(root).into_iter()
+            .filter(|n| !n.is_back && note_space.is_file_exist(&n.name))
+            .collect();
+        let notes = tree.iter().map(|n| n.name.clone()).collect();
+        Self { tree, notes }
+    }
+
+    fn sidebar(&self, name: &str) -> String {
+        let prefix = root_prefix(name);
+        let mut s = String::from("<ul>\n");
+        for n in &self.tree {
+            let class = if n.name == name {" class=\"current\""} else {""};
+            s += &format!(
+                "<li style=\"padding-left: {}em\"><a href=\"{}{}\"{}>{}</a></li>\n",
+                n.deep, prefix, page_url(&n.name), class, html_escape(&NoteSpace::base_name(&n.name)),
+            );
+        }
+        s + "</ul>\n"
+    }
+
+    fn page(&self, name: &str, body: &str) -> String {
+        let prefix = root_prefix(name);
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+            <link rel=\"stylesheet\" href=\"{prefix}{STYLE_FILE}\">\n</head>\n\
+            <body data-root=\"{prefix}\">\n<nav>\n<input id=\"search\" placeholder=\"Search\">\n<div id=\"results\"></div>\n{sidebar}</nav>\n\
+            <main>\n{body}</main>\n\
+            <script src=\"{prefix}{SEARCH_FILE}\"></script>\n<script>\n{SEARCH_SCRIPT}\n</script>\n</body>\n</html>\n",
+            title = html_escape(&NoteSpace::base_name(name)),
+            sidebar = self.sidebar(name),
+        )
+    }
+
+    /// write pages, images and search index to out_dir, return count of pages
+    /// out_dir can't be in the work dir, the notes and images would be overwritten
+    pub fn export(&self, note_space: &NoteSpace, out_dir: &Path) -> std::io::Result<usize> {
+        if abs_dir(out_dir)?.starts_with(std::fs::canonicalize(note_space.work_dir())?) {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "the export dir is in the workspace"));
+        }
+        std::fs::create_dir_all(out_dir)?;
+        let re_tag = Regex::new(r"<[^>]*>").unwrap();
+        let mut search = vec![];
+        let mut done = HashSet::new();
+        for n in &self.tree {
+            //a note with several parents is in the tree more than once
+            if !done.insert(n.name.clone()) {
+                continue;
+            }
+            let text = note_space.read_note(&n.name)?;
+            let (_, body) = FrontMatter::split(&text);
+            let md = rewrite_wiki_links(body, &n.name, &self.notes, |target| note_space.resolve_link(target));
+            let html = markdown::to_html_with_options(&md, &markdown::Options::gfm())
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+            let html = fix_html(&html, &root_prefix(&n.name));
+
+            let path = out_dir.join(format!("{}.html", n.name));
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(&path, self.page(&n.name, &html))?;
+
+            let plain = re_tag.replace_all(&html, " ");
+            let plain: String = plain.split_whitespace().collect::<Vec<_>>().join(" ");
+            search.push(SearchItem {
+                title: NoteSpace::base_name(&n.name),
+                url: page_url(&n.name),
+                text: plain.chars().take(SEARCH_TEXT_LEN).collect(),
+            });
+        }
+
+        //js file but not json, it can be loaded from file:// without a server
+        let json = serde_json::to_string(&search)?;
+        std::fs::write(out_dir.join(SEARCH_FILE), format!("var SEARCH_INDEX = {};\n", json))?;
+        std::fs::write(out_dir.join(STYLE_FILE), STYLE)?;
+
+        if let Some(first) = self.tree.first() {
+            let index = format!("<!DOCTYPE html>\n<meta http-equiv=\"refresh\" content=\"0; url={}\">\n", page_url(&first.name));
+            std::fs::write(out_dir.join("index.html"), index)?;
+        }
+        let images = Path::new(&note_space.image_path()).to_path_buf();
+        if images.is_dir() {
+            copy_dir(&images, &out_dir.join("images"))?;
+        }
+        Ok(done.len())
+    }
+}
+
+/// choose the dir and export the notes under root
+pub struct ExportWin {
+    is_show: bool,
+    root: String,
+    out_dir: String,
+    result: Option<String>,
+}
+
+impl ExportWin {
+    pub fn default() -> Self {
+        Self {
+            is_show: false,
+            root: String::new(),
+            out_dir: String::new(),
+            result: None,
+        }
+    }
+
+    pub fn active(&mut self, root: &str, work_dir: &Path) {
+        self.is_show = true;
+        self.root = root.to_string();
+        if self.out_dir.is_empty() {
+            self.out_dir = format!("{}_site", work_dir.display());
+        }
+        self.result = None;
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, note_space: &NoteSpace) {
+        if !self.is_show {
+            return;
+        }
+        let title = if self.root == "." {"Export all notes".to_string()} else {format!("Export {}", self.root)};
+        let mut is_show = self.is_show;
+        Window::new(title)
+            .id("export_window".into())
+            .open(&mut is_show)
+            .order(Order::TOP)
+            .show(ui.ctx(), |ui| {
+                ui.label("Notes under it in the link tree are exported as html pages.");
+                ui.horizontal(|ui| {
+                    ui.label("Dir");
+                    ui.add(TextEdit::singleline(&mut self.out_dir).desired_width(320.0));
+                });
+                if ui.button("Export").clicked() {
+                    let export = SiteExport::new(note_space, &self.root);
+                    self.result = Some(match export.export(note_space, Path::new(&self.out_dir)) {
+                        Ok(count) => format!("Exported {} notes", count),
+                        Err(e) => format!("Failed: {}", e),
+                    });
+                }
+                if let Some(result) = &self.result {
+                    ui.weak(result);
+                }
+            });
+        self.is_show = is_show;
+    }
+}
+
+#[test]
+pub fn test_export_links() {
+    let notes: HashSet<String> = ["a/b", "c d"].iter().map(|s| s.to_string()).collect();
+    let text = "[[c d]] [[a/b#My Heading|see]] [[x]] `[[a/b]]`";
+    assert_eq!(
+        rewrite_wiki_links(text, "a/note", &notes, |target| target.to_string()),
+        "[c d](<../c%20d.html>) [see](<../a/b.html#my-heading>) x `[[a/b]]`"
+    );
+    //embedded images are images, embedded notes are links
+    assert_eq!(
+        rewrite_wiki_links("![[p q.png|cap]] ![[images/r.PNG]] ![[a/b]]", "a/note", &notes, |target| target.to_string()),
+        "![cap](<p%20q.png>) ![](<r.PNG>) [a/b](<../a/b.html>)"
+    );
+    assert_eq!(fix_html("<h2>My <em>Heading</em></h2><img src=\"p.png\" alt=\"\">", "../"),
+        "<h2 id=\"my-heading\">My <em>Heading</em></h2><img src=\"../images/p.png\" alt=\"\">");
+}
+
+#[test]
+pub fn test_export_dangling_links() {
+    let dir = std::env::temp_dir().join(format!("egscribe_export_{}", std::process::id()));
+    let _ = std::fs::create_dir_all(&dir);
+    std::fs::write(dir.join("a.md"), "[[b]] [[missing]] ![[p.png]]\n").unwrap();
+    std::fs::write(dir.join("b.md"), "# B\n").unwrap();
+
+    let space = NoteSpace::new(dir.clone());
+    let site = SiteExport::new(&space, "a");
+    assert_eq!(site.tree.iter().map(|n| n.name.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+    let out_dir = std::env::temp_dir().join(format!("egscribe_export_site_{}", std::process::id()));
+    assert_eq!(site.export(&space, &out_dir).unwrap(), 2);
+    let page = std::fs::read_to_string(out_dir.join("a.html")).unwrap();
+    assert!(page.contains("<img src=\"images/p.png\""));
+    //the notes and images can't be overwritten
+    assert!(site.export(&space, &dir).is_err());
+    assert!(site.export(&space, &dir.join("images")).is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+    let _ = std::fs::remove_dir_all(&out_dir);
+}
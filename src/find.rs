@@ -95,6 +95,12 @@ impl FindWindow {
                 param = Some(ctx)
             }
 
+            if ui.button("find in notes").clicked() {
+                let mut ctx: FindReplaceCtx = self.param.clone();
+                ctx.cmd = Some(FindCmd::FindInNotes);
+                param = Some(ctx)
+            }
+
             ui.separator();
             let open_text = if self.is_open_replace {"<"} else {">"};
             let open_replace_button = Button::new(open_text).selected(self.is_open_replace).rounding(3.0);
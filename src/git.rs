@@ -0,0 +1,592 @@
+use serde::{Serialize, Deserialize};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use chrono::{DateTime, Local};
+use eframe::egui::{Order, ScrollArea, Ui, Window};
+use git2::{Index, IndexEntry, IndexTime, Oid, Repository, Signature, Sort, Status, StatusOptions};
+use crate::medit::{self, Ctx};
+
+/// files of work dir which are not committed
+const IGNORED: [&str; 4] = [".trash", ".history", "index.json", "config.json"];
+/// names listed in generated message, the others are counted
+const MESSAGE_NAMES: usize = 5;
+const MAX_LOG: usize = 200;
+
+/// settings of auto commit, it's off until the user turns it on
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GitCfg {
+    pub auto_commit: bool,
+    pub commit_on_save: bool,   //or commit on interval
+    pub interval_mins: u64,
+}
+
+impl Default for GitCfg {
+    fn default() -> Self {
+        Self {
+            auto_commit: false,
+            commit_on_save: false,
+            interval_mins: 10,
+        }
+    }
+}
+
+/// a commit which changed the note
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub id: Oid,
+    pub time: i64,
+    pub author: String,
+    pub summary: String,
+}
+
+pub struct BlameLine {
+    pub id: Oid,
+    pub time: i64,
+    pub author: String,
+    pub text: String,
+}
+
+fn io_err(e: git2::Error) -> std::io::Error {
+    std::io::Error::other(e.message().to_string())
+}
+
+pub fn time_text(time: i64) -> String {
+    DateTime::from_timestamp(time, 0)
+        .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+pub fn short_id(id: &Oid) -> String {
+    id.to_string()[..7].to_string()
+}
+
+/// "Update a, b; add c; delete d"
+pub fn commit_message(added: &[String], modified: &[String], deleted: &[String]) -> String {
+    let mut parts = vec![];
+    for (verb, names) in [("update", modified), ("add", added), ("delete", deleted)] {
+        if names.is_empty() {
+            continue;
+        }
+        let mut part = format!("{} {}", verb, names.iter().take(MESSAGE_NAMES).cloned().collect::<Vec<_>>().join(", "));
+        if names.len() > MESSAGE_NAMES {
+            part += &format!(" and {} more", names.len() - MESSAGE_NAMES);
+        }
+        parts.push(part);
+    }
+    let message = parts.join("; ");
+    let mut chars = message.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().collect::<String>() + chars.as_str(),
+        None => "Update notes".to_string(),
+    }
+}
+
+/// git repository holding the work dir, the work dir may be a sub dir of it
+pub struct NoteRepo {
+    repo: Repository,
+    prefix: String,     //work dir relative to the repository, "" or "notes/"
+}
+
+impl NoteRepo {
+    /// find the repository of work dir
+    pub fn open(work_dir: &Path) -> Option<Self> {
+        let repo = Repository::discover(work_dir).ok()?;
+        Self::with_repo(repo, work_dir)
+    }
+
+    /// create a repository in work dir
+    pub fn init(work_dir: &Path) -> std::io::Result<Self> {
+        let repo = Repository::init(work_dir).map_err(io_err)?;
+        let ignore_file = work_dir.join(".gitignore");
+        if !ignore_file.exists() {
+            let lines: Vec<String> = IGNORED.iter().map(|x| format!("/{}", x)).collect();
+            std::fs::write(ignore_file, lines.join("\n") + "\n")?;
+        }
+        Self::with_repo(repo, work_dir).ok_or(std::io::Error::other("bare repository"))
+    }
+
+    fn with_repo(repo: Repository, work_dir: &Path) -> Option<Self> {
+        let repo_dir = std::fs::canonicalize(repo.workdir()?).ok()?;
+        let work_dir = std::fs::canonicalize(work_dir).ok()?;
+        let rel = work_dir.strip_prefix(&repo_dir).ok()?;
+        let mut prefix = rel.to_string_lossy().replace('\\', "/");
+        if !prefix.is_empty() {
+            prefix.push('/');
+        }
+        Some(Self { repo, prefix })
+    }
+
+    /// the repository is the work dir itself, not a repository which holds it as a sub dir
+    /// only this one is committed automatically
+    pub fn is_own(&self) -> bool {
+        self.prefix.is_empty()
+    }
+
+    /// path in repository of note
+    fn note_path(&self, name: &str) -> String {
+        format!("{}{}.md", self.prefix, name)
+    }
+
+    /// file in work dir which should be committed, path is relative to repository
+    fn is_tracked(&self, path: &str) -> bool {
+        let Some(rel) = path.strip_prefix(&self.prefix) else {
+            return false;
+        };
+        let first = rel.split('/').next().unwrap_or_default();
+        !IGNORED.contains(&first)
+    }
+
+    //note name, or the file name if not a note
+    fn display_name(&self, path: &str) -> String {
+        let rel = path.strip_prefix(&self.prefix).unwrap_or(path);
+        rel.strip_suffix(".md").unwrap_or(rel).to_string()
+    }
+
+    /// (added, modified, deleted) in work dir
+    fn changes(&self) -> std::io::Result<(Vec<String>, Vec<String>, Vec<String>)> {
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(true).recurse_untracked_dirs(true);
+        if !self.prefix.is_empty() {
+            opts.pathspec(&self.prefix);
+        }
+        let statuses = self.repo.statuses(Some(&mut opts)).map_err(io_err)?;
+        let (mut added, mut modified, mut deleted) = (vec![], vec![], vec![]);
+        for entry in statuses.iter() {
+            let Some(path) = entry.path() else {
+                continue;
+            };
+            if !self.is_tracked(path) {
+                continue;
+            }
+            let name = self.display_name(path);
+            let status = entry.status();
+            if status.intersects(Status::WT_NEW | Status::INDEX_NEW) {
+                added.push(name);
+            } else if status.intersects(Status::WT_DELETED | Status::INDEX_DELETED) {
+                deleted.push(name);
+            } else if status.intersects(Status::WT_MODIFIED | Status::INDEX_MODIFIED | Status::WT_RENAMED | Status::INDEX_RENAMED | Status::WT_TYPECHANGE) {
+                modified.push(name);
+            }
+        }
+        Ok((added, modified, deleted))
+    }
+
+    /// files of work dir to commit, (path in repository, blob)
+    /// dir: path of rel on disk, rel: "" or "sub/" relative to repository
+    fn collect_files(&self, dir: &Path, rel: &str, files: &mut Vec<(String, Oid)>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)?.flatten() {
+            let file_type = entry.file_type()?;
+            let path = format!("{}{}", rel, entry.file_name().to_string_lossy());
+            if file_type.is_symlink() || entry.file_name() == ".git" || !self.is_managed(&path) {
+                continue;
+            }
+            if file_type.is_dir() {
+                self.collect_files(&entry.path(), &format!("{}/", path), files)?;
+            } else {
+                let id = self.repo.blob_path(&entry.path()).map_err(io_err)?;
+                files.push((path, id));
+            }
+        }
+        Ok(())
+    }
+
+    fn index_entry(path: &str, id: Oid) -> IndexEntry {
+        IndexEntry {
+            ctime: IndexTime::new(0, 0),
+            mtime: IndexTime::new(0, 0),
+            dev: 0,
+            ino: 0,
+            mode: 0o100644,
+            uid: 0,
+            gid: 0,
+            file_size: 0,
+            id,
+            flags: path.len().min(0xfff) as u16,
+            flags_extended: 0,
+            path: path.as_bytes().to_vec(),
+        }
+    }
+
+    /// path which collect_files commits when it is in work dir
+    fn is_managed(&self, path: &str) -> bool {
+        self.is_tracked(path) && !self.repo.status_should_ignore(Path::new(path)).unwrap_or(false)
+    }
+
+    /// remove the entries of work dir which aren't in files, the ignored ones are kept
+    fn remove_deleted_files(&self, index: &mut Index, files: &[(String, Oid)]) -> std::io::Result<()> {
+        let deleted: Vec<String> = index.iter()
+            .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+            .filter(|path| self.is_managed(path) && !files.iter().any(|(p, _)| p == path))
+            .collect();
+        for path in deleted {
+            index.remove_path(Path::new(&path)).map_err(io_err)?;
+        }
+        Ok(())
+    }
+
+    /// commit all changes of work dir with a generated message, None if nothing changed
+    /// the tree is HEAD with the files of work dir, what the user staged out of work dir isn't committed
+    pub fn commit_all(&self) -> std::io::Result<Option<Oid>> {
+        let (added, modified, deleted) = self.changes()?;
+        if added.is_empty() && modified.is_empty() && deleted.is_empty() {
+            return Ok(None);
+        }
+
+        let repo_dir = self.repo.workdir().ok_or(std::io::Error::other("bare repository"))?;
+        let mut files = vec![];
+        self.collect_files(&repo_dir.join(&self.prefix), &self.prefix, &mut files)?;
+
+        let parent = self.repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let mut index = Index::new().map_err(io_err)?;
+        if let Some(parent) = &parent {
+            index.read_tree(&parent.tree().map_err(io_err)?).map_err(io_err)?;
+        }
+        self.remove_deleted_files(&mut index, &files)?;
+        for (path, id) in &files {
+            index.add(&Self::index_entry(path, *id)).map_err(io_err)?;
+        }
+        let tree_id = index.write_tree_to(&self.repo).map_err(io_err)?;
+        let tree = self.repo.find_tree(tree_id).map_err(io_err)?;
+
+        //index of repository is the same as the commit in work dir
+        //add_path fills the stat data, so the files aren't dirty after commit
+        let mut repo_index = self.repo.index().map_err(io_err)?;
+        self.remove_deleted_files(&mut repo_index, &files)?;
+        for (path, _) in &files {
+            repo_index.add_path(Path::new(path)).map_err(io_err)?;
+        }
+        repo_index.write().map_err(io_err)?;
+
+        if parent.as_ref().is_some_and(|p| p.tree_id() == tree_id) {
+            return Ok(None);
+        }
+        let signature = self.repo.signature()
+            .or(Signature::now("egscribe", "egscribe@localhost"))
+            .map_err(io_err)?;
+        let message = commit_message(&added, &modified, &deleted);
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        let id = self.repo.commit(Some("HEAD"), &signature, &signature, &message, &tree, &parents).map_err(io_err)?;
+        Ok(Some(id))
+    }
+
+    fn blob_id_at(&self, commit: &git2::Commit, path: &str) -> Option<Oid> {
+        commit.tree().ok()?.get_path(Path::new(path)).ok().map(|e| e.id())
+    }
+
+    /// commits which changed the note, the latest first
+    pub fn log(&self, name: &str) -> std::io::Result<Vec<LogEntry>> {
+        let path = self.note_path(name);
+        let mut walk = self.repo.revwalk().map_err(io_err)?;
+        walk.set_sorting(Sort::TIME).map_err(io_err)?;
+        if walk.push_head().is_err() {
+            return Ok(vec![]);    //no commit yet
+        }
+        let mut entries = vec![];
+        for id in walk.flatten() {
+            let Ok(commit) = self.repo.find_commit(id) else {
+                continue;
+            };
+            let blob = self.blob_id_at(&commit, &path);
+            let parent_blob = commit.parent(0).ok().and_then(|p| self.blob_id_at(&p, &path));
+            if blob.is_none() || blob == parent_blob {
+                continue;
+            }
+            entries.push(LogEntry {
+                id,
+                time: commit.time().seconds(),
+                author: commit.author().name().unwrap_or_default().to_string(),
+                summary: commit.summary().unwrap_or_default().to_string(),
+            });
+            if entries.len() >= MAX_LOG {
+                break;
+            }
+        }
+        Ok(entries)
+    }
+
+    /// text of note in the commit
+    pub fn read_at(&self, name: &str, id: Oid) -> std::io::Result<String> {
+        let commit = self.repo.find_commit(id).map_err(io_err)?;
+        let blob_id = self.blob_id_at(&commit, &self.note_path(name))
+            .ok_or(std::io::Error::new(std::io::ErrorKind::NotFound, name))?;
+        let blob = self.repo.find_blob(blob_id).map_err(io_err)?;
+        Ok(String::from_utf8_lossy(blob.content()).to_string())
+    }
+
+    /// the last commit of each line, of the committed text
+    pub fn blame(&self, name: &str) -> std::io::Result<Vec<BlameLine>> {
+        let path = self.note_path(name);
+        let head = self.repo.head().and_then(|h| h.peel_to_commit()).map_err(io_err)?;
+        let text = self.read_at(name, head.id())?;
+        let blame = self.repo.blame_file(Path::new(&path), None).map_err(io_err)?;
+        let lines = text.lines().enumerate().map(|(i, line)| {
+            let hunk = blame.get_line(i + 1);
+            BlameLine {
+                id: hunk.as_ref().map(|h| h.final_commit_id()).unwrap_or(Oid::zero()),
+                time: hunk.as_ref().map(|h| h.final_signature().when().seconds()).unwrap_or_default(),
+                author: hunk.as_ref().and_then(|h| h.final_signature().name().map(|n| n.to_string())).unwrap_or_default(),
+                text: line.to_string(),
+            }
+        }).collect();
+        Ok(lines)
+    }
+}
+
+type CommitResult = std::io::Result<Option<Oid>>;
+
+/// commit the work dir in a background thread, one at a time
+pub struct GitCommitter {
+    receiver: Option<Receiver<CommitResult>>,
+}
+
+impl GitCommitter {
+    pub fn default() -> Self {
+        Self { receiver: None }
+    }
+
+    /// return false if the last commit is still running
+    pub fn start(&mut self, work_dir: &Path) -> bool {
+        if self.receiver.is_some() {
+            return false;
+        }
+        let (sender, receiver) = channel();
+        let work_dir = PathBuf::from(work_dir);
+        std::thread::spawn(move || {
+            let result = match NoteRepo::open(&work_dir) {
+                Some(repo) => repo.commit_all(),
+                None => Ok(None),
+            };
+            let _ = sender.send(result);
+        });
+        self.receiver = Some(receiver);
+        true
+    }
+
+    /// result of the finished commit
+    pub fn poll(&mut self) -> Option<CommitResult> {
+        let received = self.receiver.as_ref()?.try_recv();
+        match received {
+            Ok(result) => {
+                self.receiver = None;
+                Some(result)
+            }
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.receiver = None;
+                None
+            }
+        }
+    }
+}
+
+#[derive(PartialEq)]
+enum GitTab {
+    Log,
+    Blame,
+}
+
+/// log and blame of current note, an old version is shown in a read-only editor
+pub struct GitWin {
+    is_show: bool,
+    tab: GitTab,
+    name: String,
+    log: Vec<LogEntry>,
+    blame: Vec<BlameLine>,
+    error: Option<String>,
+    selected: Option<Oid>,
+    old_ctx: Option<Ctx>,
+}
+
+impl GitWin {
+    pub fn default() -> Self {
+        Self {
+            is_show: false,
+            tab: GitTab::Log,
+            name: String::new(),
+            log: vec![],
+            blame: vec![],
+            error: None,
+            selected: None,
+            old_ctx: None,
+        }
+    }
+
+    pub fn active(&mut self, name: &str, repo: &NoteRepo) {
+        self.is_show = true;
+        self.name = name.to_string();
+        self.selected = None;
+        self.old_ctx = None;
+        self.error = None;
+        match repo.log(name) {
+            Ok(log) => self.log = log,
+            Err(e) => self.error = Some(e.to_string()),
+        }
+        self.blame = repo.blame(name).unwrap_or_default();
+    }
+
+    fn select(&mut self, id: Oid, repo: &NoteRepo, image_path: &str) {
+        self.selected = Some(id);
+        self.old_ctx = match repo.read_at(&self.name, id) {
+            Ok(text) => {
+                let mut ctx = Ctx::new(&text, true, Some(image_path.to_string()));
+                ctx.cfg_mut().read_only = true;
+                Some(ctx)
+            }
+            Err(e) => {
+                self.error = Some(e.to_string());
+                None
+            }
+        };
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, repo: Option<&NoteRepo>, image_path: &str) {
+        if !self.is_show {
+            return;
+        }
+        let Some(repo) = repo else {
+            self.is_show = false;
+            return;
+        };
+
+        let mut is_show = self.is_show;
+        let mut to_select = None;
+        let egui_ctx = ui.ctx();
+        Window::new(format!("git of {}", self.name))
+            .id("git_window".into())
+            .open(&mut is_show)
+            .default_size([640.0, 460.0])
+            .order(Order::TOP)
+            .show(egui_ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.tab, GitTab::Log, format!("log ({})", self.log.len()));
+                    ui.selectable_value(&mut self.tab, GitTab::Blame, "blame");
+                    if let Some(error) = &self.error {
+                        ui.colored_label(ui.visuals().error_fg_color, error);
+                    }
+                });
+                ui.separator();
+
+                match self.tab {
+                    GitTab::Log => {
+                        ScrollArea::vertical().id_source("git_log").max_height(140.0).auto_shrink([false, true]).show(ui, |ui| {
+                            for entry in &self.log {
+                                let text = format!("{}  {}  {}  {}", short_id(&entry.id), time_text(entry.time), entry.author, entry.summary);
+                                if ui.selectable_label(self.selected == Some(entry.id), text).clicked() {
+                                    to_select = Some(entry.id);
+                                }
+                            }
+                            if self.log.is_empty() {
+                                ui.weak("Not committed yet.");
+                            }
+                        });
+                        if let Some(ctx) = &mut self.old_ctx {
+                            ui.separator();
+                            ui.weak("read-only");
+                            ui.add(medit::Edit::new(ctx));
+                        }
+                    }
+                    GitTab::Blame => {
+                        ScrollArea::both().id_source("git_blame").auto_shrink([false, false]).show(ui, |ui| {
+                            for line in &self.blame {
+                                ui.horizontal(|ui| {
+                                    let info = format!("{} {:<12.12} {}", short_id(&line.id), line.author, time_text(line.time));
+                                    if ui.link(eframe::egui::RichText::new(info).monospace().weak()).clicked() {
+                                        to_select = Some(line.id);
+                                    }
+                                    ui.monospace(&line.text);
+                                });
+                            }
+                            if self.blame.is_empty() {
+                                ui.weak("Not committed yet.");
+                            }
+                        });
+                    }
+                }
+            });
+        self.is_show = is_show;
+
+        if let Some(id) = to_select {
+            self.tab = GitTab::Log;
+            self.select(id, repo, image_path);
+        }
+    }
+}
+
+#[test]
+pub fn test_note_repo() {
+    assert_eq!(commit_message(&[], &["a".into()], &[]), "Update a");
+    let many: Vec<String> = (1..=7).map(|i| i.to_string()).collect();
+    assert_eq!(commit_message(&["n".into()], &[], &many), "Add n; delete 1, 2, 3, 4, 5 and 2 more");
+
+    let dir = std::env::temp_dir().join(format!("egscribe_git_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let repo = NoteRepo::init(&dir).unwrap();
+    std::fs::write(dir.join("a.md"), "one\n").unwrap();
+    std::fs::write(dir.join("index.json"), "{}").unwrap();
+    let first = repo.commit_all().unwrap().unwrap();
+    assert_eq!(repo.commit_all().unwrap(), None);
+
+    std::fs::write(dir.join("a.md"), "one\ntwo\n").unwrap();
+    repo.commit_all().unwrap().unwrap();
+    let log = repo.log("a").unwrap();
+    assert_eq!(log.len(), 2);
+    assert_eq!(log[0].summary, "Update a");
+    assert_eq!(repo.read_at("a", first).unwrap(), "one\n");
+    let blame = repo.blame("a").unwrap();
+    assert_eq!(blame[0].id, first);
+    assert_eq!(blame[1].id, log[0].id);
+
+    //ignored files are not committed
+    let head = repo.repo.head().unwrap().peel_to_commit().unwrap();
+    assert!(head.tree().unwrap().get_path(Path::new("index.json")).is_err());
+
+    //a committed config.json is kept, a deleted note is removed, the index is clean after commit
+    std::fs::write(dir.join("config.json"), "{}").unwrap();
+    let mut index = repo.repo.index().unwrap();
+    index.add_path(Path::new("config.json")).unwrap();
+    index.write().unwrap();
+    let tree = repo.repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let sig = Signature::now("t", "t@localhost").unwrap();
+    let head = repo.repo.head().unwrap().peel_to_commit().unwrap();
+    repo.repo.commit(Some("HEAD"), &sig, &sig, "config", &tree, &[&head]).unwrap();
+    std::fs::write(dir.join("b.md"), "b\n").unwrap();
+    repo.commit_all().unwrap().unwrap();
+    std::fs::remove_file(dir.join("b.md")).unwrap();
+    let id = repo.commit_all().unwrap().unwrap();
+    let tree = repo.repo.find_commit(id).unwrap().tree().unwrap();
+    assert!(tree.get_path(Path::new("config.json")).is_ok());
+    assert!(tree.get_path(Path::new("b.md")).is_err());
+    assert!(repo.repo.index().unwrap().get_path(Path::new("config.json"), 0).is_some());
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(false);
+    assert!(repo.repo.statuses(Some(&mut opts)).unwrap().iter().all(|e| e.status() == Status::CURRENT));
+    assert!(repo.is_own());
+    assert!(!GitCfg::default().auto_commit);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+pub fn test_note_repo_in_sub_dir() {
+    let dir = std::env::temp_dir().join(format!("egscribe_git_sub_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("docs")).unwrap();
+    let outer = Repository::init(&dir).unwrap();
+    std::fs::write(dir.join("code.rs"), "fn main() {}\n").unwrap();
+    let mut index = outer.index().unwrap();
+    index.add_path(Path::new("code.rs")).unwrap();
+    index.write().unwrap();
+
+    //the staged file of user is kept staged, only the notes are committed
+    std::fs::write(dir.join("docs/a.md"), "one\n").unwrap();
+    let repo = NoteRepo::open(&dir.join("docs")).unwrap();
+    assert!(!repo.is_own());
+    let id = repo.commit_all().unwrap().unwrap();
+    let tree = outer.find_commit(id).unwrap().tree().unwrap();
+    assert!(tree.get_path(Path::new("docs/a.md")).is_ok());
+    assert!(tree.get_path(Path::new("code.rs")).is_err());
+    let index = outer.index().unwrap();
+    assert!(index.get_path(Path::new("code.rs"), 0).is_some());
+    assert_eq!(repo.commit_all().unwrap(), None);
+    let _ = std::fs::remove_dir_all(&dir);
+}
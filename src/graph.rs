@@ -0,0 +1,522 @@
+use std::collections::{HashMap, VecDeque};
+use eframe::egui::{self, Align2, FontId, Pos2, Rect, Sense, Shape, Stroke, Ui, Vec2, Window};
+use crate::medit::Command;
+use crate::space::NoteSpace;
+
+const NODE_RADIUS: f32 = 6.0;
+const SPRING_LEN: f32 = 80.0;
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 5.0;
+
+struct GraphNode {
+    name: String,
+    pos: Vec2,      //graph position, (0,0) is the center of view
+    vel: Vec2,
+    in_cycle: bool,
+    is_orphan: bool,
+}
+
+struct GraphEdge {
+    from: usize,
+    to: usize,
+    in_cycle: bool,
+}
+
+/// force-directed graph of note links
+pub struct GraphView {
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+    data_version: usize,
+    pan: Vec2,
+    zoom: f32,
+    depth: usize,               //0: show all notes
+    focus: Option<String>,      //center of depth filter, None: current note
+    drag_node: Option<usize>,
+    temperature: f32,           //max move of a simulation step
+}
+
+/// find nodes and edges in cycles with tarjan's strongly connected components
+/// return (node in cycle, scc id of node)
+pub fn find_cycles(adj: &[Vec<usize>]) -> (Vec<bool>, Vec<usize>) {
+    struct Tarjan<'a> {
+        adj: &'a [Vec<usize>],
+        index: usize,
+        indexes: Vec<Option<usize>>,
+        lowlink: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        scc: Vec<usize>,
+        scc_count: usize,
+        in_cycle: Vec<bool>,
+    }
+
+    impl Tarjan<'_> {
+        fn visit(&mut self, v: usize) {
+            self.indexes[v] = Some(self.index);
+            self.lowlink[v] = self.index;
+            self.index += 1;
+            self.stack.push(v);
+            self.on_stack[v] = true;
+        }
+
+        /// iterative, a long chain of links doesn't overflow the stack
+        /// calls: (node, index of next edge) as the frames of recursion
+        fn connect(&mut self, root: usize) {
+            self.visit(root);
+            let mut calls = vec![(root, 0)];
+            while let Some((v, edge)) = calls.last_mut() {
+                let v = *v;
+                if let Some(&w) = self.adj[v].get(*edge) {
+                    *edge += 1;
+                    match self.indexes[w] {
+                        None => {
+                            self.visit(w);
+                            calls.push((w, 0));
+                        }
+                        Some(w_index) if self.on_stack[w] => {
+                            self.lowlink[v] = self.lowlink[v].min(w_index);
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+
+                //all edges done, return to the caller
+                calls.pop();
+                if let Some(&(u, _)) = calls.last() {
+                    self.lowlink[u] = self.lowlink[u].min(self.lowlink[v]);
+                }
+                if Some(self.lowlink[v]) == self.indexes[v] {
+                    let mut members = vec![];
+                    while let Some(w) = self.stack.pop() {
+                        self.on_stack[w] = false;
+                        self.scc[w] = self.scc_count;
+                        members.push(w);
+                        if w == v {
+                            break;
+                        }
+                    }
+                    let is_cycle = members.len() > 1 || self.adj[v].contains(&v);
+                    for w in members {
+                        self.in_cycle[w] = is_cycle;
+                    }
+                    self.scc_count += 1;
+                }
+            }
+        }
+    }
+
+    let n = adj.len();
+    let mut tarjan = Tarjan {
+        adj,
+        index: 0,
+        indexes: vec![None; n],
+        lowlink: vec![0; n],
+        on_stack: vec![false; n],
+        stack: vec![],
+        scc: vec![0; n],
+        scc_count: 0,
+        in_cycle: vec![false; n],
+    };
+    for v in 0..n {
+        if tarjan.indexes[v].is_none() {
+            tarjan.connect(v);
+        }
+    }
+    (tarjan.in_cycle, tarjan.scc)
+}
+
+impl GraphView {
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![],
+            edges: vec![],
+            data_version: 0,
+            pan: Vec2::ZERO,
+            zoom: 1.0,
+            depth: 0,
+            focus: None,
+            drag_node: None,
+            temperature: SPRING_LEN,
+        }
+    }
+
+    /// rebuild nodes and edges from links, keep the position of old nodes
+    fn rebuild(&mut self, note_space: &NoteSpace) {
+        let old_pos: HashMap<String, Vec2> = self.nodes.iter().map(|n| (n.name.clone(), n.pos)).collect();
+        let names = note_space.get_all_notes();
+        let index: HashMap<&String, usize> = names.iter().enumerate().map(|(i, n)| (n, i)).collect();
+
+        let mut adj = vec![vec![]; names.len()];
+        for (i, name) in names.iter().enumerate() {
+            for link in note_space.get_child_links(name) {
+                if let Some(&j) = index.get(&link) {
+                    if !adj[i].contains(&j) {
+                        adj[i].push(j);
+                    }
+                }
+            }
+        }
+        let (in_cycle, scc) = find_cycles(&adj);
+
+        let mut has_link = vec![false; names.len()];
+        self.edges = vec![];
+        for (from, tos) in adj.iter().enumerate() {
+            for &to in tos {
+                has_link[from] = true;
+                has_link[to] = true;
+                self.edges.push(GraphEdge {
+                    from,
+                    to,
+                    in_cycle: scc[from] == scc[to] && in_cycle[from],
+                });
+            }
+        }
+
+        let count = names.len().max(1) as f32;
+        self.nodes = names.into_iter().enumerate().map(|(i, name)| {
+            //new node is placed on a circle
+            let angle = i as f32 / count * std::f32::consts::TAU;
+            let pos = old_pos.get(&name).cloned()
+                .unwrap_or(Vec2::angled(angle) * SPRING_LEN * count.sqrt());
+            GraphNode {
+                name,
+                pos,
+                vel: Vec2::ZERO,
+                in_cycle: in_cycle[i],
+                is_orphan: !has_link[i],
+            }
+        }).collect();
+
+        self.data_version = note_space.data_version();
+        self.temperature = SPRING_LEN;
+    }
+
+    /// nodes within depth of focus node, ignore direction of links
+    fn visible_nodes(&self, focus: Option<&String>) -> Vec<bool> {
+        let n = self.nodes.len();
+        let focus = focus.and_then(|f| self.nodes.iter().position(|x| &x.name == f));
+        let Some(focus) = focus.filter(|_| self.depth > 0) else {
+            return vec![true; n];
+        };
+
+        let mut neighbors = vec![vec![]; n];
+        for e in &self.edges {
+            neighbors[e.from].push(e.to);
+            neighbors[e.to].push(e.from);
+        }
+        let mut deep = vec![usize::MAX; n];
+        let mut queue = VecDeque::new();
+        deep[focus] = 0;
+        queue.push_back(focus);
+        while let Some(v) = queue.pop_front() {
+            if deep[v] >= self.depth {
+                continue;
+            }
+            for &w in &neighbors[v] {
+                if deep[w] == usize::MAX {
+                    deep[w] = deep[v] + 1;
+                    queue.push_back(w);
+                }
+            }
+        }
+        deep.iter().map(|d| *d != usize::MAX).collect()
+    }
+
+    /// one step of force-directed layout
+    fn simulate(&mut self, visible: &[bool]) {
+        if self.temperature < 0.5 {
+            return;
+        }
+        let n = self.nodes.len();
+        let mut force = vec![Vec2::ZERO; n];
+
+        //repulsion between all visible nodes
+        for i in 0..n {
+            if !visible[i] {
+                continue;
+            }
+            for j in (i + 1)..n {
+                if !visible[j] {
+                    continue;
+                }
+                let mut delta = self.nodes[i].pos - self.nodes[j].pos;
+                if delta.length_sq() < 0.01 {
+                    delta = Vec2::new((i as f32).sin(), (j as f32).cos());
+                }
+                let dist = delta.length().max(1.0);
+                let f = delta / dist * (SPRING_LEN * SPRING_LEN / dist);
+                force[i] += f;
+                force[j] -= f;
+            }
+        }
+
+        //spring of links
+        for e in &self.edges {
+            if e.from == e.to || !visible[e.from] || !visible[e.to] {
+                continue;
+            }
+            let delta = self.nodes[e.to].pos - self.nodes[e.from].pos;
+            let dist = delta.length().max(1.0);
+            let f = delta / dist * (dist * dist / SPRING_LEN) * 0.5;
+            force[e.from] += f;
+            force[e.to] -= f;
+        }
+
+        //move, limited by temperature
+        for (i, node) in self.nodes.iter_mut().enumerate() {
+            if !visible[i] || Some(i) == self.drag_node {
+                continue;
+            }
+            let gravity = -node.pos * 0.02;
+            node.vel = (node.vel + (force[i] + gravity) * 0.05) * 0.6;
+            let len = node.vel.length();
+            if len > self.temperature {
+                node.vel *= self.temperature / len;
+            }
+            node.pos += node.vel;
+        }
+        self.temperature *= 0.98;
+    }
+
+    fn to_screen(&self, rect: &Rect, pos: Vec2) -> Pos2 {
+        rect.center() + self.pan + pos * self.zoom
+    }
+
+    fn node_at(&self, rect: &Rect, visible: &[bool], pointer: Pos2) -> Option<usize> {
+        let radius = (NODE_RADIUS * self.zoom).max(4.0) + 2.0;
+        self.nodes.iter().enumerate()
+            .filter(|(i, _)| visible[*i])
+            .find(|(_, node)| self.to_screen(rect, node.pos).distance(pointer) <= radius)
+            .map(|(i, _)| i)
+    }
+
+    fn draw_arrow(ui: &Ui, from: Pos2, to: Pos2, radius: f32, stroke: Stroke) {
+        let dir = (to - from).normalized();
+        let from = from + dir * radius;
+        let tip = to - dir * radius;
+        ui.painter().line_segment([from, tip], stroke);
+        let side = Vec2::new(-dir.y, dir.x) * 3.0;
+        let back = tip - dir * 7.0;
+        ui.painter().add(Shape::convex_polygon(vec![tip, back + side, back - side], stroke.color, Stroke::NONE));
+    }
+
+    fn show_graph(&mut self, ui: &mut Ui, note_space: &NoteSpace) -> Option<Command> {
+        let mut cmd = None;
+        let current = note_space.get_current_note();
+        let focus = self.focus.clone().or(current.clone());
+        let visible = self.visible_nodes(focus.as_ref());
+
+        let (rect, response) = ui.allocate_exact_size(ui.available_size(), Sense::click_and_drag());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+
+        //pan and drag node
+        if response.drag_started() {
+            if let Some(pointer) = response.interact_pointer_pos() {
+                self.drag_node = self.node_at(&rect, &visible, pointer);
+            }
+        }
+        if response.dragged() {
+            let delta = response.drag_delta();
+            if let Some(i) = self.drag_node {
+                self.nodes[i].pos += delta / self.zoom;
+                self.temperature = self.temperature.max(SPRING_LEN * 0.2);
+            } else {
+                self.pan += delta;
+            }
+        }
+        if response.drag_stopped() {
+            self.drag_node = None;
+        }
+
+        //zoom around pointer
+        if let Some(hover) = response.hover_pos() {
+            let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+            if scroll != 0.0 {
+                let old_zoom = self.zoom;
+                self.zoom = (self.zoom * (scroll * 0.002).exp()).clamp(MIN_ZOOM, MAX_ZOOM);
+                let anchor = hover - rect.center() - self.pan;
+                self.pan -= anchor * (self.zoom / old_zoom - 1.0);
+            }
+        }
+
+        //click to open note
+        let hover_node = response.hover_pos().and_then(|p| self.node_at(&rect, &visible, p));
+        if response.clicked() {
+            if let Some(i) = hover_node {
+                cmd = Some(Command::OpenFile(self.nodes[i].name.clone()));
+            }
+        }
+
+        self.simulate(&visible);
+        if self.temperature >= 0.5 {
+            ui.ctx().request_repaint();
+        }
+
+        //neighbors of current note
+        let cur_index = current.as_ref().and_then(|c| self.nodes.iter().position(|n| &n.name == c));
+        let mut is_neighbor = vec![false; self.nodes.len()];
+        if let Some(c) = cur_index {
+            for e in &self.edges {
+                if e.from == c {
+                    is_neighbor[e.to] = true;
+                }
+                if e.to == c {
+                    is_neighbor[e.from] = true;
+                }
+            }
+        }
+
+        let visuals = ui.visuals();
+        let weak = visuals.weak_text_color();
+        let strong = visuals.strong_text_color();
+        let highlight = visuals.selection.bg_fill;
+        let cycle_color = visuals.warn_fg_color;
+        let radius = (NODE_RADIUS * self.zoom).max(3.0);
+
+        //edges
+        for e in &self.edges {
+            if !visible[e.from] || !visible[e.to] {
+                continue;
+            }
+            let from = self.to_screen(&rect, self.nodes[e.from].pos);
+            let to = self.to_screen(&rect, self.nodes[e.to].pos);
+            let is_cur = Some(e.from) == cur_index || Some(e.to) == cur_index;
+            let color = if e.in_cycle { cycle_color } else if is_cur { highlight } else { weak };
+            let stroke = Stroke::new(if is_cur { 1.5 } else { 1.0 }, color);
+            if e.from == e.to {
+                painter.circle_stroke(from + Vec2::new(radius, -radius), radius, stroke);
+            } else {
+                Self::draw_arrow(ui, from, to, radius, stroke);
+            }
+        }
+
+        //nodes
+        let font = FontId::proportional((11.0 * self.zoom.sqrt()).clamp(8.0, 18.0));
+        for (i, node) in self.nodes.iter().enumerate() {
+            if !visible[i] {
+                continue;
+            }
+            let pos = self.to_screen(&rect, node.pos);
+            if !rect.expand(radius).contains(pos) {
+                continue;
+            }
+            let is_cur = Some(i) == cur_index;
+            let fill = if is_cur {
+                highlight
+            } else if is_neighbor[i] {
+                strong
+            } else if node.in_cycle {
+                cycle_color
+            } else {
+                weak
+            };
+            if node.is_orphan {
+                painter.circle_stroke(pos, radius, Stroke::new(1.5, fill));
+            } else {
+                painter.circle_filled(pos, radius, fill);
+            }
+            if is_cur || is_neighbor[i] || Some(i) == hover_node || self.zoom > 0.8 {
+                let color = if is_cur || Some(i) == hover_node { strong } else { weak };
+                painter.text(pos + Vec2::new(0.0, radius + 2.0), Align2::CENTER_TOP, NoteSpace::base_name(&node.name), font.clone(), color);
+            }
+        }
+
+        if let Some(i) = hover_node {
+            response.on_hover_text(&self.nodes[i].name);
+        }
+        cmd
+    }
+
+    fn show_content(&mut self, ui: &mut Ui, note_space: &NoteSpace, is_show: &mut bool) -> Option<Command> {
+        let mut cmd = None;
+        ui.horizontal(|ui| {
+            if ui.small_button("close").clicked() {
+                *is_show = false;
+            }
+            ui.separator();
+            ui.label("depth");
+            let mut depth = self.depth;
+            ui.add(egui::Slider::new(&mut depth, 0..=6).custom_formatter(|d, _| {
+                if d == 0.0 { "all".to_string() } else { format!("{}", d) }
+            }));
+            if depth != self.depth {
+                self.depth = depth;
+                self.temperature = SPRING_LEN;
+            }
+            let focus_text = match &self.focus {
+                Some(focus) => format!("focus: {}", focus),
+                None => "focus: current".to_string(),
+            };
+            ui.menu_button(focus_text, |ui| {
+                if ui.button("current note").clicked() {
+                    ui.close_menu();
+                    self.focus = None;
+                }
+                let names: Vec<String> = self.nodes.iter().map(|n| n.name.clone()).collect();
+                egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                    for name in names {
+                        if ui.button(&name).clicked() {
+                            ui.close_menu();
+                            self.focus = Some(name);
+                        }
+                    }
+                });
+            });
+            if ui.small_button("reset view").clicked() {
+                self.pan = Vec2::ZERO;
+                self.zoom = 1.0;
+                self.temperature = SPRING_LEN;
+            }
+            let orphans = self.nodes.iter().filter(|n| n.is_orphan).count();
+            let cycles = self.nodes.iter().filter(|n| n.in_cycle).count();
+            ui.weak(format!("notes: {}  orphans: {}  in cycles: {}", self.nodes.len(), orphans, cycles));
+        });
+        ui.separator();
+        if let Some(c) = self.show_graph(ui, note_space) {
+            cmd = Some(c);
+        }
+        cmd
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, note_space: &NoteSpace, is_show: &mut bool) -> Option<Command> {
+        let mut cmd = None;
+        if !*is_show {
+            return cmd;
+        }
+        if self.data_version != note_space.data_version() {
+            self.rebuild(note_space);
+        }
+
+        let mut open = true;
+        Window::new("graph")
+            .default_size([640.0, 480.0])
+            .title_bar(false)
+            .resizable(true)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                cmd = self.show_content(ui, note_space, is_show);
+            });
+        cmd
+    }
+}
+
+#[test]
+pub fn test_find_cycles() {
+    // 0 -> 1 -> 2 -> 0, 2 -> 3, 4 -> 4
+    let adj = vec![vec![1], vec![2], vec![0, 3], vec![], vec![4]];
+    let (in_cycle, scc) = find_cycles(&adj);
+    assert_eq!(in_cycle, vec![true, true, true, false, true]);
+    assert_eq!(scc[0], scc[1]);
+    assert_eq!(scc[1], scc[2]);
+    assert!(scc[2] != scc[3]);
+
+    //a long chain doesn't overflow the stack
+    let n = 200_000;
+    let mut adj: Vec<Vec<usize>> = (0..n).map(|i| vec![i + 1]).collect();
+    adj[n - 1] = vec![0];
+    let (in_cycle, scc) = find_cycles(&adj);
+    assert!(in_cycle.iter().all(|&c| c));
+    assert!(scc.iter().all(|&id| id == scc[0]));
+}
@@ -0,0 +1,230 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use chrono::{DateTime, Local};
+use eframe::egui::{Order, ScrollArea, Ui, Window};
+use crate::medit::Ctx;
+use crate::watch::ConflictWin;
+
+/// dir of note versions in the work dir, it's hidden from the index tree
+pub const HISTORY_DIR: &str = ".history";
+/// saves within this time replace the latest version
+const MIN_INTERVAL: u64 = 5 * 60 * 1000;
+/// the oldest versions are removed
+const MAX_VERSIONS: usize = 100;
+
+/// one saved version of note
+#[derive(Debug, Clone)]
+pub struct Version {
+    pub time: u64,      //unix time in millis
+    path: PathBuf,
+}
+
+impl Version {
+    pub fn time_text(&self) -> String {
+        DateTime::from_timestamp_millis(self.time as i64)
+            .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// versions of each note are kept in ".history/<name>/<millis>.md", "/" of name is escaped
+pub struct NoteHistory {
+    dir: PathBuf,
+}
+
+impl NoteHistory {
+    pub fn new(work_dir: &std::path::Path) -> Self {
+        Self { dir: work_dir.join(HISTORY_DIR) }
+    }
+
+    fn note_dir(&self, name: &str) -> PathBuf {
+        self.dir.join(name.replace('%', "%25").replace('/', "%2F"))
+    }
+
+    /// versions of note, the latest first
+    pub fn versions(&self, name: &str) -> Vec<Version> {
+        let mut versions: Vec<Version> = std::fs::read_dir(self.note_dir(name))
+            .map(|dir| dir.flatten()
+                .filter_map(|entry| {
+                    let path = entry.path();
+                    let time = path.file_stem()?.to_str()?.parse::<u64>().ok()?;
+                    Some(Version { time, path })
+                })
+                .collect())
+            .unwrap_or_default();
+        versions.sort_by_key(|v| std::cmp::Reverse(v.time));
+        versions
+    }
+
+    pub fn read(&self, version: &Version) -> std::io::Result<String> {
+        std::fs::read_to_string(&version.path)
+    }
+
+    /// record the saved text, return false if it's the same as the latest
+    pub fn snapshot(&self, name: &str, text: &str) -> std::io::Result<bool> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+        self.snapshot_at(name, text, now)
+    }
+
+    fn snapshot_at(&self, name: &str, text: &str, now: u64) -> std::io::Result<bool> {
+        let dir = self.note_dir(name);
+        let mut versions = self.versions(name);
+        if let Some(latest) = versions.first() {
+            if self.read(latest).is_ok_and(|t| t == text) {
+                return Ok(false);
+            }
+        }
+        //throttled, the window starts at the version before the latest which is always kept,
+        //only the latest written inside the window is replaced
+        if versions.len() > 1 && now < versions[1].time + MIN_INTERVAL {
+            std::fs::remove_file(&versions[0].path)?;
+            versions.remove(0);
+        }
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join(format!("{}.md", now)), text)?;
+
+        for old in versions.iter().skip(MAX_VERSIONS - 1) {
+            let _ = std::fs::remove_file(&old.path);
+        }
+        Ok(true)
+    }
+
+    /// versions follow the renamed note
+    pub fn rename(&self, org: &str, new: &str) -> std::io::Result<()> {
+        let from = self.note_dir(org);
+        if !from.exists() {
+            return Ok(());
+        }
+        std::fs::rename(from, self.note_dir(new))
+    }
+}
+
+/// versions of current note, diff against current text and restore
+pub struct HistoryWin {
+    is_show: bool,
+    name: String,
+    versions: Vec<Version>,
+    selected: Option<usize>,
+    selected_text: String,
+    cur_text: String,       //text of editor when the version is selected
+}
+
+impl HistoryWin {
+    pub fn default() -> Self {
+        Self {
+            is_show: false,
+            name: String::new(),
+            versions: vec![],
+            selected: None,
+            selected_text: String::new(),
+            cur_text: String::new(),
+        }
+    }
+
+    pub fn active(&mut self, name: &str, history: &NoteHistory) {
+        self.is_show = true;
+        self.name = name.to_string();
+        self.versions = history.versions(name);
+        self.selected = None;
+    }
+
+    //return the text of version to restore
+    pub fn show(&mut self, ui: &mut Ui, history: &NoteHistory, ctx: Option<&Ctx>) -> Option<String> {
+        if !self.is_show {
+            return None;
+        }
+
+        let mut restore = None;
+        let mut is_show = self.is_show;
+        let egui_ctx = ui.ctx();
+        Window::new(format!("history of {}", self.name))
+            .id("history_window".into())
+            .open(&mut is_show)
+            .default_size([560.0, 400.0])
+            .order(Order::TOP)
+            .show(egui_ctx, |ui| {
+                if self.versions.is_empty() {
+                    ui.weak("No versions, a version is recorded when the note is saved.");
+                    return;
+                }
+                ui.horizontal_top(|ui| {
+                    ScrollArea::vertical().id_source("history_versions").max_width(160.0).show(ui, |ui| {
+                        ui.vertical(|ui| {
+                            for (i, version) in self.versions.iter().enumerate() {
+                                if ui.selectable_label(self.selected == Some(i), version.time_text()).clicked() {
+                                    self.selected = Some(i);
+                                    self.selected_text = history.read(version).unwrap_or_default();
+                                    self.cur_text = ctx.map(|c| c.get_all_text()).unwrap_or_default();
+                                }
+                            }
+                        });
+                    });
+                    ui.separator();
+                    ui.vertical(|ui| {
+                        if self.selected.is_none() {
+                            ui.weak("Select a version to compare with current text.");
+                            return;
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.button("restore").on_hover_text("Replace current text, it can be undone").clicked() {
+                                restore = Some(self.selected_text.clone());
+                            }
+                            ui.weak("- current  + version");
+                        });
+                        ui.separator();
+                        ScrollArea::both().id_source("history_diff").auto_shrink([false, false]).show(ui, |ui| {
+                            if self.cur_text == self.selected_text {
+                                ui.weak("Same as current text.");
+                            } else {
+                                ConflictWin::show_diff_lines(ui, &self.cur_text, &self.selected_text);
+                            }
+                        });
+                    });
+                });
+            });
+        self.is_show = is_show;
+
+        if restore.is_some() {
+            self.is_show = false;
+        }
+        restore
+    }
+}
+
+#[test]
+pub fn test_note_history() {
+    let dir = std::env::temp_dir().join(format!("egscribe_history_{}", std::process::id()));
+    let history = NoteHistory::new(&dir);
+    let name = "folder/note";
+    assert!(history.snapshot_at(name, "a", 1000).unwrap());
+    //same text is skipped
+    assert!(!history.snapshot_at(name, "a", 2000).unwrap());
+    //the version before the window is kept
+    assert!(history.snapshot_at(name, "b", 3000).unwrap());
+    //throttled, only the version inside the window is replaced
+    assert!(history.snapshot_at(name, "c", 4000).unwrap());
+    let versions = history.versions(name);
+    assert_eq!(versions.iter().map(|v| v.time).collect::<Vec<_>>(), vec![4000, 1000]);
+    assert_eq!(history.read(&versions[0]).unwrap(), "c");
+
+    assert!(history.snapshot_at(name, "d", 1000 + MIN_INTERVAL).unwrap());
+    let versions = history.versions(name);
+    assert_eq!(versions.iter().map(|v| v.time).collect::<Vec<_>>(), vec![1000 + MIN_INTERVAL, 4000, 1000]);
+
+    history.rename(name, "note").unwrap();
+    assert!(history.versions(name).is_empty());
+    assert_eq!(history.versions("note").len(), 3);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+pub fn test_history_keep_good_save() {
+    let dir = std::env::temp_dir().join(format!("egscribe_history_good_{}", std::process::id()));
+    let history = NoteHistory::new(&dir);
+    let start = 10 * MIN_INTERVAL;
+    assert!(history.snapshot_at("note", "good", start).unwrap());
+    assert!(history.snapshot_at("note", "bad", start + 60 * 1000).unwrap());
+    let texts: Vec<String> = history.versions("note").iter().map(|v| history.read(v).unwrap()).collect();
+    assert_eq!(texts, vec!["bad", "good"]);
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,438 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use eframe::egui::{Order, TextEdit, Ui, Window};
+use regex::Regex;
+use crate::links::insert_link_line;
+use crate::medit::{FrontMatter, MarkDownImpl, WikiLink};
+use crate::space::NoteSpace;
+
+/// attachments with these extensions are shown as images
+const IMAGE_EXTS: [&str; 7] = ["png", "jpg", "jpeg", "gif", "bmp", "webp", "svg"];
+/// block properties of logseq which are only for its outline
+const LOGSEQ_BLOCK_PROPS: [&str; 2] = ["id", "collapsed"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VaultKind {
+    Obsidian,
+    Logseq,
+}
+
+impl VaultKind {
+    pub fn detect(dir: &Path) -> Self {
+        if dir.join("logseq").is_dir() || (dir.join("pages").is_dir() && !dir.join(".obsidian").is_dir()) {
+            VaultKind::Logseq
+        } else {
+            VaultKind::Obsidian
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VaultKind::Obsidian => "Obsidian",
+            VaultKind::Logseq => "Logseq",
+        }
+    }
+}
+
+/// a markdown file of vault
+#[derive(Clone, Debug)]
+struct VaultNote {
+    path: PathBuf,
+    title: String,          //name used by links in the vault
+    rel: String,            //note name in the import folder
+    is_journal: bool,
+}
+
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| IMAGE_EXTS.contains(&ext.as_str()))
+}
+
+fn file_key(name: &str) -> String {
+    let name = name.replace("%20", " ");
+    name.rsplit(['/', '\\']).next().unwrap_or(&name).to_lowercase()
+}
+
+/// "a___b" or "a%2Fb" is the page "a/b" of logseq
+fn logseq_title(stem: &str) -> String {
+    stem.replace("___", "/").replace("%2F", "/").replace("%2f", "/")
+}
+
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        //.obsidian, .trash, .git and the config dir of logseq
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') || name == "logseq" {
+            continue;
+        }
+        if path.is_dir() {
+            walk(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// logseq page properties "key:: value" at the top become frontmatter
+fn logseq_properties(text: &str) -> (String, &str) {
+    let mut front = FrontMatter::new();
+    let mut end = 0;
+    for line in text.split_inclusive('\n') {
+        let Some((key, value)) = line.trim().split_once(":: ") else {
+            break;
+        };
+        if key.is_empty() || key.contains(' ') {
+            break;
+        }
+        end += line.len();
+        if key != "title" {
+            front.set(key, &value.replace("[[", "").replace("]]", ""));
+        }
+    }
+    if front.pairs().is_empty() {
+        return (String::new(), &text[end..]);
+    }
+    (front.to_text(), text[end..].trim_start_matches(['\r', '\n']))
+}
+
+/// copy a vault into a folder of work dir, attachments are moved to images
+pub struct VaultImport {
+    pub kind: VaultKind,
+    notes: Vec<VaultNote>,
+    attachments: Vec<PathBuf>,
+}
+
+impl VaultImport {
+    pub fn scan(src: &Path, kind: VaultKind) -> std::io::Result<Self> {
+        let mut files = vec![];
+        walk(src, &mut files)?;
+        files.sort();
+        let mut notes = vec![];
+        let mut attachments = vec![];
+        for path in files {
+            let is_md = path.extension().is_some_and(|ext| ext == "md");
+            if !is_md {
+                attachments.push(path);
+                continue;
+            }
+            let rel_path = path.strip_prefix(src).unwrap_or(&path).with_extension("");
+            let rel = rel_path.to_string_lossy().replace('\\', "/");
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+            let note = match kind {
+                VaultKind::Obsidian => VaultNote { path, title: rel.clone(), rel, is_journal: false },
+                VaultKind::Logseq => {
+                    let is_journal = rel.starts_with("journals/");
+                    let title = logseq_title(&stem);
+                    let rel = if is_journal {format!("journals/{}", stem)} else {title.clone()};
+                    VaultNote { path, title, rel, is_journal }
+                }
+            };
+            notes.push(note);
+        }
+        Ok(Self { kind, notes, attachments })
+    }
+
+    pub fn note_count(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn attachment_count(&self) -> usize {
+        self.attachments.len()
+    }
+
+    /// links may use the full name or only the base name in obsidian, title of page in logseq
+    fn name_map(&self, folder: &str) -> HashMap<String, String> {
+        let mut map = HashMap::new();
+        for note in &self.notes {
+            let name = NoteSpace::join_name(folder, &note.rel);
+            map.entry(NoteSpace::base_name(&note.title).to_lowercase()).or_insert(name.clone());
+            map.insert(note.title.to_lowercase(), name);
+        }
+        map
+    }
+
+    /// wiki links get the new names, embedded images become markdown images
+    pub fn convert_text(text: &str, kind: VaultKind, names: &HashMap<String, String>, images: &HashMap<String, String>) -> String {
+        let (front, body) = match kind {
+            VaultKind::Logseq => logseq_properties(text),
+            VaultKind::Obsidian => (String::new(), text),
+        };
+        let mut text = body.to_string();
+        if kind == VaultKind::Logseq {
+            let lines: Vec<&str> = text.lines()
+                .filter(|line| !line.trim().split_once(":: ").is_some_and(|(key, _)| LOGSEQ_BLOCK_PROPS.contains(&key)))
+                .collect();
+            text = lines.join("\n") + if text.ends_with('\n') {"\n"} else {""};
+        }
+
+        for link in MarkDownImpl::wiki_links(&text).iter().rev() {
+            let new_text = if let Some(image) = images.get(&file_key(&link.target)).filter(|_| link.embed) {
+                format!("![]({})", image)
+            } else if let Some(name) = names.get(&link.target.to_lowercase()) {
+                WikiLink { target: name.clone(), ..link.clone() }.to_text()
+            } else {
+                continue;
+            };
+            text.replace_range(link.range.clone(), &new_text);
+        }
+
+        //local images of markdown, "![](assets/a.png)"
+        let re = Regex::new(r"!\[([^\]]*)\]\(<?([^)>]+?)>?\)").unwrap();
+        let text = re.replace_all(&text, |cap: &regex::Captures| {
+            let url = &cap[2];
+            match images.get(&file_key(url)).filter(|_| !url.contains("://")) {
+                Some(image) => format!("![{}]({})", &cap[1], image),
+                None => cap[0].to_string(),
+            }
+        });
+        front + &text
+    }
+
+    /// copy attachments to image dir with unique names, return file name -> new name
+    fn copy_attachments(&self, image_dir: &Path) -> std::io::Result<HashMap<String, String>> {
+        std::fs::create_dir_all(image_dir)?;
+        let mut map = HashMap::new();
+        for path in &self.attachments {
+            let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            //spaces are not loaded in the url of image
+            let clean = name.replace(' ', "_");
+            let (stem, ext) = match clean.rsplit_once('.') {
+                Some((stem, ext)) => (stem.to_string(), format!(".{}", ext)),
+                None => (clean.clone(), String::new()),
+            };
+            let mut new_name = clean.clone();
+            let mut i = 1;
+            while image_dir.join(&new_name).exists() {
+                new_name = format!("{}_{}{}", stem, i, ext);
+                i += 1;
+            }
+            std::fs::copy(path, image_dir.join(&new_name))?;
+            if is_image(path) {
+                map.entry(file_key(&name)).or_insert(new_name);
+            }
+        }
+        Ok(map)
+    }
+
+    /// write notes to the folder and a root note which links the top notes, return the name of root
+    pub fn import(&self, note_space: &NoteSpace, folder: &str) -> std::io::Result<String> {
+        if folder.trim().is_empty() || note_space.is_folder_exist(folder) {
+            return Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, format!("folder \"{}\" exists", folder)));
+        }
+        let images = self.copy_attachments(Path::new(&note_space.image_path()))?;
+        let names = self.name_map(folder);
+
+        let mut texts: Vec<(String, String, bool)> = vec![];    //(name, text, is_journal)
+        for note in &self.notes {
+            let text = std::fs::read_to_string(&note.path)?;
+            let name = NoteSpace::join_name(folder, &note.rel);
+            texts.push((name, Self::convert_text(&text, self.kind, &names, &images), note.is_journal));
+        }
+
+        //logseq namespace "a/b" is a child of page "a"
+        if self.kind == VaultKind::Logseq {
+            let pages: HashSet<String> = texts.iter().map(|t| t.0.clone()).collect();
+            for i in 0..texts.len() {
+                let parent = NoteSpace::folder_of(&texts[i].0);
+                if !texts[i].2 && pages.contains(&parent) {
+                    let child = texts[i].0.clone();
+                    if let Some(t) = texts.iter_mut().find(|t| t.0 == parent) {
+                        t.1 = insert_link_line(&t.1, &child, "", true);
+                    }
+                }
+            }
+        }
+
+        let mut root_name = NoteSpace::join_name(folder, &NoteSpace::base_name(folder));
+        while texts.iter().any(|t| t.0 == root_name) {
+            root_name += "_root";
+        }
+        let (pages, journals): (Vec<_>, Vec<_>) = texts.iter().partition(|t| !t.2);
+        let page_names: Vec<String> = pages.iter().map(|t| t.0.clone()).collect();
+        let page_texts: HashMap<&str, &str> = pages.iter().map(|t| (t.0.as_str(), t.1.as_str())).collect();
+        let top = Self::top_notes(&page_names, &page_texts);
+
+        let mut root = format!("# {}\n\nImported from {}\n\n", NoteSpace::base_name(folder), self.kind.name());
+        for name in &top {
+            root += &format!("[[{}]]\n", name);
+        }
+        if !journals.is_empty() {
+            root += "\n## Journals\n\n";
+            let mut journals: Vec<&String> = journals.iter().map(|t| &t.0).collect();
+            journals.sort_by(|a, b| b.cmp(a));
+            for name in journals {
+                root += &format!("[[{}]]\n", name);
+            }
+        }
+
+        for (name, text, _) in &texts {
+            note_space.write_note(name, text)?;
+        }
+        note_space.write_note(&root_name, &root)?;
+        Ok(root_name)
+    }
+
+    /// notes linked from root, all the notes can be reached from them
+    fn top_notes(names: &[String], texts: &HashMap<&str, &str>) -> Vec<String> {
+        let children: HashMap<&str, Vec<String>> = texts.iter()
+            .map(|(name, text)| {
+                let links = MarkDownImpl::wiki_links(text).into_iter()
+                    .map(|link| link.target)
+                    .filter(|target| target != name && texts.contains_key(target.as_str()))
+                    .collect();
+                (*name, links)
+            })
+            .collect();
+        let linked: HashSet<&String> = children.values().flatten().collect();
+
+        //notes without parent, then one of each cycle
+        let mut top: Vec<String> = names.iter().filter(|n| !linked.contains(n)).cloned().collect();
+        let mut reached: HashSet<String> = HashSet::new();
+        let mut stack = top.clone();
+        loop {
+            while let Some(name) = stack.pop() {
+                if reached.insert(name.clone()) {
+                    stack.extend(children.get(name.as_str()).cloned().unwrap_or_default());
+                }
+            }
+            match names.iter().find(|n| !reached.contains(*n)) {
+                Some(name) => {
+                    top.push(name.clone());
+                    stack.push(name.clone());
+                }
+                None => break,
+            }
+        }
+        top
+    }
+}
+
+/// choose the vault dir, check it and import
+pub struct ImportWin {
+    is_show: bool,
+    dir: String,
+    folder: String,
+    scan: Option<VaultImport>,
+    message: Option<String>,
+    root: Option<String>,
+}
+
+impl ImportWin {
+    pub fn default() -> Self {
+        Self {
+            is_show: false,
+            dir: String::new(),
+            folder: String::new(),
+            scan: None,
+            message: None,
+            root: None,
+        }
+    }
+
+    pub fn active(&mut self) {
+        self.is_show = true;
+        self.scan = None;
+        self.message = None;
+        self.root = None;
+    }
+
+    fn do_scan(&mut self, kind: Option<VaultKind>) {
+        let dir = PathBuf::from(self.dir.trim());
+        let kind = kind.unwrap_or_else(|| VaultKind::detect(&dir));
+        match VaultImport::scan(&dir, kind) {
+            Ok(scan) => {
+                if self.folder.is_empty() {
+                    self.folder = dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                }
+                self.scan = Some(scan);
+                self.message = None;
+            }
+            Err(e) => {
+                self.scan = None;
+                self.message = Some(format!("Failed: {}", e));
+            }
+        }
+    }
+
+    /// return the root note after imported, the index should be rebuilt
+    pub fn show(&mut self, ui: &mut Ui, note_space: &NoteSpace) -> Option<String> {
+        if !self.is_show {
+            return None;
+        }
+        let mut imported = None;
+        let mut is_show = self.is_show;
+        Window::new("Import vault")
+            .id("import_window".into())
+            .open(&mut is_show)
+            .order(Order::TOP)
+            .show(ui.ctx(), |ui| {
+                //step 1, the dir of vault
+                ui.horizontal(|ui| {
+                    ui.label("Vault dir");
+                    ui.add(TextEdit::singleline(&mut self.dir).desired_width(280.0));
+                    if ui.button("Scan").clicked() {
+                        self.folder.clear();
+                        self.do_scan(None);
+                    }
+                });
+
+                //step 2, check and import
+                let mut rescan = None;
+                if let Some(scan) = &self.scan {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        for kind in [VaultKind::Obsidian, VaultKind::Logseq] {
+                            if ui.radio(scan.kind == kind, kind.name()).clicked() && scan.kind != kind {
+                                rescan = Some(kind);
+                            }
+                        }
+                    });
+                    ui.label(format!("{} notes, {} attachments", scan.note_count(), scan.attachment_count()));
+                    ui.horizontal(|ui| {
+                        ui.label("Import to folder");
+                        ui.text_edit_singleline(&mut self.folder);
+                    });
+                    ui.weak("Attachments are copied to images, a root note links the imported notes.");
+                    if self.root.is_none() && ui.button("Import").clicked() {
+                        match scan.import(note_space, self.folder.trim()) {
+                            Ok(root) => {
+                                self.message = Some(format!("Imported {} notes", scan.note_count()));
+                                self.root = Some(root.clone());
+                                imported = Some(root);
+                            }
+                            Err(e) => self.message = Some(format!("Failed: {}", e)),
+                        }
+                    }
+                }
+                if rescan.is_some() {
+                    self.do_scan(rescan);
+                }
+                if let Some(message) = &self.message {
+                    ui.weak(message);
+                }
+            });
+        self.is_show = is_show;
+        imported
+    }
+}
+
+#[test]
+pub fn test_import_convert() {
+    let names: HashMap<String, String> = [("note", "v/dir/Note"), ("dir/note", "v/dir/Note")].iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    let images: HashMap<String, String> = [("a b.png", "a_b.png")].iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+    let text = "[[Note#Sec|see]] ![[a b.png|300]] ![x](attachments/a%20b.png) [[Other]] ![[Note]]\n";
+    assert_eq!(VaultImport::convert_text(text, VaultKind::Obsidian, &names, &images),
+        "[[v/dir/Note#Sec|see]] ![](a_b.png) ![x](a_b.png) [[Other]] ![[v/dir/Note]]\n");
+
+    let text = "title:: Page\ntags:: [[x]], y\n\n- block [[note]]\n  id:: 6512\n  - child ![](../assets/a b.png)\n";
+    assert_eq!(VaultImport::convert_text(text, VaultKind::Logseq, &names, &images),
+        "---\ntags: x, y\n---\n\n- block [[v/dir/Note]]\n  - child ![](a_b.png)\n");
+}
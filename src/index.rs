@@ -0,0 +1,223 @@
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use crate::medit::ctx::EditCfg;
+use crate::medit::{FindReplaceCtx, FrontMatter, MarkDownImpl};
+
+//change it when the format of entry changed, the old index will be rebuilt
+const INDEX_VERSION: u32 = 5;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct IndexEntry {
+    pub mtime: u64,             //modified time in millis
+    pub ctime: u64,             //created time in millis, mtime if the file system has no created time
+    pub size: u64,
+    pub links: Vec<String>,
+    pub headings: Vec<String>,
+    pub tags: Vec<String>,
+    pub properties: Vec<(String, String)>,  //frontmatter
+    pub terms: Vec<String>,     //lowercase words, sorted and deduped
+}
+
+impl IndexEntry {
+    pub fn from_text(text: &str, mtime: u64, size: u64) -> Self {
+        let cfg = EditCfg::new(17.0, true, None);
+        let markdown = MarkDownImpl::new_simple(text, &cfg);
+        Self {
+            mtime,
+            ctime: mtime,
+            size,
+            links: markdown.markdown_get_links(),
+            headings: markdown.markdown_get_headings(),
+            tags: markdown.markdown_get_tags(),
+            properties: FrontMatter::parse(text).map(|f| f.pairs()).unwrap_or_default(),
+            terms: NoteIndex::split_terms(text),
+        }
+    }
+}
+
+/// index of all notes, saved as index.json next to config.json
+/// entries are updated by modified time of files
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct NoteIndex {
+    version: u32,
+    notes: HashMap<String, IndexEntry>,
+    #[serde(skip)]
+    term_notes: HashMap<String, Vec<String>>,   //term -> notes
+}
+
+impl NoteIndex {
+    pub fn new() -> Self {
+        Self {
+            version: INDEX_VERSION,
+            notes: HashMap::new(),
+            term_notes: HashMap::new(),
+        }
+    }
+
+    pub fn load(file: &str) -> Self {
+        if let Ok(json_str) = std::fs::read_to_string(file) {
+            if let Ok(mut index) = serde_json::from_str::<NoteIndex>(&json_str) {
+                if index.version == INDEX_VERSION {
+                    index.rebuild_terms();
+                    return index;
+                }
+            }
+        }
+        Self::new()
+    }
+
+    pub fn save(&self, file: &str) {
+        if let Ok(json_str) = serde_json::to_string(self) {
+            let _ = std::fs::write(file, json_str);
+        }
+    }
+
+    fn file_stamp(path: &Path) -> Option<(u64, u64)> {
+        let meta = std::fs::metadata(path).ok()?;
+        let mtime = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64;
+        Some((mtime, meta.len()))
+    }
+
+    fn created_time(path: &Path) -> Option<u64> {
+        let ctime = std::fs::metadata(path).ok()?.created().ok()?;
+        Some(ctime.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64)
+    }
+
+    /// the entry is the same as the file on disk
+    pub fn is_fresh(&self, name: &str, path: &Path) -> bool {
+        match (self.notes.get(name), Self::file_stamp(path)) {
+            (Some(entry), Some((mtime, size))) => entry.mtime == mtime && entry.size == size,
+            _ => false,
+        }
+    }
+
+    /// reparse the changed files and remove the deleted, return true if changed
+    pub fn update(&mut self, files: &[(String, PathBuf)]) -> bool {
+        let mut changed = false;
+        let names: HashSet<&String> = files.iter().map(|(name, _)| name).collect();
+        let count = self.notes.len();
+        self.notes.retain(|name, _| names.contains(name));
+        if count != self.notes.len() {
+            changed = true;
+        }
+
+        for (name, path) in files {
+            if self.is_fresh(name, path) {
+                continue;
+            }
+            let (mtime, size) = Self::file_stamp(path).unwrap_or((0, 0));
+            let text = std::fs::read_to_string(path).unwrap_or_default();
+            let mut entry = IndexEntry::from_text(&text, mtime, size);
+            entry.ctime = Self::created_time(path).unwrap_or(mtime);
+            self.notes.insert(name.clone(), entry);
+            changed = true;
+        }
+
+        if changed {
+            self.rebuild_terms();
+        }
+        changed
+    }
+
+    fn rebuild_terms(&mut self) {
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        for (name, entry) in &self.notes {
+            for term in &entry.terms {
+                map.entry(term.clone()).or_default().push(name.clone());
+            }
+        }
+        self.term_notes = map;
+    }
+
+    pub fn split_terms(text: &str) -> Vec<String> {
+        let mut terms: Vec<String> = text.to_lowercase()
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .filter(|t| !t.is_empty())
+            .map(|t| t.to_string())
+            .collect();
+        terms.sort();
+        terms.dedup();
+        terms
+    }
+
+    pub fn links(&self, name: &str) -> Vec<String> {
+        self.notes.get(name).map(|e| e.links.clone()).unwrap_or_default()
+    }
+
+    pub fn tags(&self, name: &str) -> Vec<String> {
+        self.notes.get(name).map(|e| e.tags.clone()).unwrap_or_default()
+    }
+
+    pub fn properties(&self, name: &str) -> Vec<(String, String)> {
+        self.notes.get(name).map(|e| e.properties.clone()).unwrap_or_default()
+    }
+
+    /// (modified, created) time in millis, 0 if not indexed
+    pub fn times(&self, name: &str) -> (u64, u64) {
+        self.notes.get(name).map_or((0, 0), |e| (e.mtime, e.ctime))
+    }
+
+    /// notes which may match the find param, None if all notes need to be searched
+    pub fn candidates(&self, param: &FindReplaceCtx) -> Option<HashSet<String>> {
+        if param.is_reg {
+            return None;
+        }
+        let words = Self::split_terms(&param.find);
+        if words.is_empty() {
+            return None;
+        }
+
+        let mut result: Option<HashSet<String>> = None;
+        for word in &words {
+            //every word of find text is a part of some term in note
+            let notes: HashSet<String> = self.term_notes.iter()
+                .filter(|(term, _)| term.contains(word.as_str()))
+                .flat_map(|(_, notes)| notes.iter().cloned())
+                .collect();
+            result = Some(match result {
+                Some(r) => r.intersection(&notes).cloned().collect(),
+                None => notes,
+            });
+        }
+        result
+    }
+}
+
+#[test]
+pub fn test_note_index() {
+    let dir = std::env::temp_dir().join(format!("egscribe_index_{}", std::process::id()));
+    let _ = std::fs::create_dir_all(&dir);
+    let a = dir.join("a.md");
+    let b = dir.join("b.md");
+    std::fs::write(&a, "# Title A\nhello [[b]] world").unwrap();
+    std::fs::write(&b, "## Sub\nHelloWorld foo").unwrap();
+    let files = vec![("a".to_string(), a.clone()), ("b".to_string(), b.clone())];
+
+    let mut index = NoteIndex::new();
+    assert!(index.update(&files));
+    assert!(!index.update(&files));
+    assert_eq!(index.links("a"), vec!["b".to_string()]);
+    assert_eq!(index.notes["a"].headings, vec!["Title A".to_string()]);
+    assert_eq!(index.notes["b"].headings, vec!["Sub".to_string()]);
+
+    let mut param = FindReplaceCtx::new();
+    param.find = "hello".to_string();
+    assert_eq!(index.candidates(&param).unwrap().len(), 2);
+    param.find = "hello fo".to_string();
+    assert_eq!(index.candidates(&param).unwrap().len(), 1);
+    param.find = "foo".to_string();
+    assert!(index.candidates(&param).unwrap().contains("b"));
+
+    let file = dir.join("index.json").to_string_lossy().to_string();
+    index.save(&file);
+    let loaded = NoteIndex::load(&file);
+    assert!(loaded.is_fresh("a", &a));
+    assert_eq!(loaded.candidates(&param).unwrap().len(), 1);
+
+    let files = vec![("a".to_string(), a)];
+    assert!(index.update(&files));
+    assert!(!index.is_fresh("b", &b));
+    let _ = std::fs::remove_dir_all(&dir);
+}
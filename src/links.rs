@@ -0,0 +1,124 @@
+use eframe::egui::{Order, ScrollArea, Ui, Window};
+use crate::medit::MarkDownImpl;
+
+/// parents changed by rewriting links after a note is renamed or deleted
+pub struct LinkReport {
+    is_show: bool,
+    title: String,
+    edits: Vec<(String, usize)>,    //(parent, count of links)
+}
+
+impl LinkReport {
+    pub fn default() -> Self {
+        Self {
+            is_show: false,
+            title: String::new(),
+            edits: vec![],
+        }
+    }
+
+    pub fn start(&mut self, title: &str) {
+        self.is_show = false;
+        self.title = title.to_string();
+        self.edits.clear();
+    }
+
+    pub fn add(&mut self, file: &str, count: usize) {
+        if let Some(edit) = self.edits.iter_mut().find(|(f, _)| f == file) {
+            edit.1 += count;
+        } else {
+            self.edits.push((file.to_string(), count));
+        }
+        self.is_show = true;
+    }
+
+    /// links are changed without a report, such as a new note is named by its heading
+    pub fn hide(&mut self) {
+        self.is_show = false;
+    }
+
+    //return the note clicked
+    pub fn show(&mut self, ui: &mut Ui) -> Option<String> {
+        if !self.is_show {
+            return None;
+        }
+        let mut clicked = None;
+        let total: usize = self.edits.iter().map(|(_, count)| count).sum();
+        let egui_ctx = ui.ctx();
+        Window::new("links updated")
+            .open(&mut self.is_show)
+            .default_size([280.0, 200.0])
+            .order(Order::TOP)
+            .show(egui_ctx, |ui| {
+                ui.label(format!("{}: {} links in {} notes", self.title, total, self.edits.len()));
+                ui.separator();
+                ScrollArea::vertical().auto_shrink([false, true]).show(ui, |ui| {
+                    for (file, count) in &self.edits {
+                        ui.horizontal(|ui| {
+                            if ui.link(file).clicked() {
+                                clicked = Some(file.clone());
+                            }
+                            ui.weak(count.to_string());
+                        });
+                    }
+                });
+            });
+        clicked
+    }
+}
+
+//line of the first [[target]] in text
+fn link_line(text: &str, target: &str) -> Option<usize> {
+    MarkDownImpl::wiki_links(text).iter()
+        .find(|link| link.target == target)
+        .map(|link| text[..link.range.start].matches('\n').count())
+}
+
+//lines without eol, and join them back with the eol of text
+fn join_lines(text: &str, lines: &[&str]) -> String {
+    let eol = if text.contains("\r\n") {"\r\n"} else {"\n"};
+    let end = if text.ends_with('\n') {eol} else {""};
+    lines.join(eol) + end
+}
+
+/// insert a line of [[note]] before or after the line of sibling link, or at the end
+pub fn insert_link_line(text: &str, note: &str, sibling: &str, after: bool) -> String {
+    let new_line = format!("[[{}]]", note);
+    match link_line(text, sibling) {
+        Some(line) => {
+            let mut lines: Vec<&str> = text.lines().collect();
+            lines.insert(line + after as usize, &new_line);
+            join_lines(text, &lines)
+        }
+        None if text.trim().is_empty() => new_line,
+        None => format!("{}\n\n{}", text.trim_end(), new_line),
+    }
+}
+
+/// move the line of [[note]] before or after the line of sibling link
+/// None if any link isn't found, or they are in the same line
+pub fn move_link_line(text: &str, note: &str, sibling: &str, after: bool) -> Option<String> {
+    let from = link_line(text, note)?;
+    let to = link_line(text, sibling)?;
+    if from == to {
+        return None;
+    }
+    let mut lines: Vec<&str> = text.lines().collect();
+    let line = lines.remove(from);
+    let to = if from < to {to - 1} else {to};
+    lines.insert(to + after as usize, line);
+    Some(join_lines(text, &lines))
+}
+
+#[test]
+pub fn test_link_lines() {
+    let text = "# Home\n[[a]]\n[[b|B]]\n- [[c]]";
+    assert_eq!(move_link_line(text, "c", "a", false).unwrap(), "# Home\n- [[c]]\n[[a]]\n[[b|B]]");
+    assert_eq!(move_link_line(text, "a", "c", true).unwrap(), "# Home\n[[b|B]]\n- [[c]]\n[[a]]");
+    assert_eq!(move_link_line(text, "a", "b", true).unwrap(), "# Home\n[[b|B]]\n[[a]]\n- [[c]]");
+    assert_eq!(move_link_line("[[a]] [[b]]", "a", "b", true), None);
+
+    assert_eq!(insert_link_line(text, "d", "b", false), "# Home\n[[a]]\n[[d]]\n[[b|B]]\n- [[c]]");
+    assert_eq!(insert_link_line("x\r\n[[a]]\r\n", "d", "a", true), "x\r\n[[a]]\r\n[[d]]\r\n");
+    assert_eq!(insert_link_line("x\n", "d", "a", true), "x\n\n[[d]]");
+}
@@ -7,20 +7,41 @@ mod toolbar;
 mod space;
 mod mem;
 mod find;
+mod workspace;
+mod backlinks;
+mod mentions;
+mod export;
+mod import;
+mod graph;
+mod search;
+mod index;
+mod watch;
+mod tags;
+mod trash;
+mod history;
+mod git;
+mod links;
+mod properties;
+mod periodic;
+mod template;
 
 use std::vec;
 use toolbar::{ToolBar, ToolBarType};
 use mem::Store;
 use find::FindWindow;
+use workspace::Workspaces;
+use backlinks::BacklinksPanel;
+use mentions::MentionsPanel;
+use graph::GraphView;
+use tags::TagsPanel;
+use trash::TrashPanel;
+use watch::{ConflictAction, ConflictWin, FileWatcher};
 use eframe::egui::{self, Color32, Stroke, Vec2};
 use eframe::egui::{Order, Rect, EventFilter, Ui, Event, Key, ScrollArea};
 
 fn main() -> Result<(), eframe::Error> {
     let args: Vec<String> = std::env::args().collect();
-    let mut file = String::new();
-    if args.len() > 1 {
-        file = args[1].clone();
-    }
+    let (workspace, file) = workspace::parse_args(&args);
 
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
     let icon = eframe::icon_data::from_png_bytes(&include_bytes!("../fonts/egscribe.png")[..]).unwrap();
@@ -35,7 +56,7 @@ fn main() -> Result<(), eframe::Error> {
         options,
         Box::new(|cc| {
             egui_extras::install_image_loaders(&cc.egui_ctx);
-            Ok(Box::new(MyApp::new(cc, file)))
+            Ok(Box::new(MyApp::new(cc, workspace, file)))
         }),
     )
 }
@@ -43,23 +64,39 @@ fn main() -> Result<(), eframe::Error> {
 struct MyApp {
     store: Store,
     find_window: FindWindow,
+    backlinks: BacklinksPanel,
+    mentions: MentionsPanel,
+    graph: GraphView,
+    tags: TagsPanel,
+    trash: TrashPanel,
+    file_watcher: FileWatcher,
+    conflict_window: ConflictWin,
     dropped_files: Vec<egui::DroppedFile>
 }
 
 impl MyApp {
-    fn new(cc: &eframe::CreationContext<'_>, file: String) -> Self {
+    fn new(cc: &eframe::CreationContext<'_>, workspace: Option<String>, file: String) -> Self {
         load_fonts(&cc.egui_ctx);
-        Self::default(file)
+        Self::default(&cc.egui_ctx, workspace, file)
     }
 
-    fn default(file: String) -> Self {
-        let mut store = Store::default();
+    fn default(egui_ctx: &egui::Context, workspace: Option<String>, file: String) -> Self {
+        let workspaces = Workspaces::load();
+        let work_dir = workspaces.resolve(workspace);
+        let mut store = Store::new(workspaces, work_dir);
         if !file.is_empty() {
             let _ = store.open_file(&file);
         }
         Self {
             store,
             find_window: FindWindow::new(),
+            backlinks: BacklinksPanel::new(),
+            mentions: MentionsPanel::new(),
+            graph: GraphView::new(),
+            tags: TagsPanel::new(),
+            trash: TrashPanel::new(),
+            file_watcher: FileWatcher::new(egui_ctx),
+            conflict_window: ConflictWin::default(),
             dropped_files: vec![],
         }
     }
@@ -94,6 +131,26 @@ impl MyApp {
                                 let _ = self.find_window.active(selected);
                             }
                         }
+                        Key::T if modifiers.alt => {
+                            //alt+t daily note of today
+                            let _ = self.store.open_periodic_today(periodic::Period::Daily);
+                        }
+                        Key::W if modifiers.alt => {
+                            //alt+w weekly note
+                            let _ = self.store.open_periodic_today(periodic::Period::Weekly);
+                        }
+                        Key::M if modifiers.alt => {
+                            //alt+m monthly note
+                            let _ = self.store.open_periodic_today(periodic::Period::Monthly);
+                        }
+                        Key::PageUp if modifiers.alt => {
+                            //alt+pageup previous periodic note
+                            let _ = self.store.open_periodic_sibling(-1);
+                        }
+                        Key::PageDown if modifiers.alt => {
+                            //alt+pagedown next periodic note
+                            let _ = self.store.open_periodic_sibling(1);
+                        }
                         Key::Escape => {
                             self.store.config_update_show_index_window(!self.store.note_space.is_show_index_window());
                         }
@@ -132,6 +189,7 @@ impl MyApp {
                 }
 
                 if let Some(edit_ctx) = self.store.cur_edit_ctx_mut() {
+                    properties::show_properties(ui, edit_ctx);
                     ui.add(medit::Edit::new(edit_ctx));
                 }
             });
@@ -164,6 +222,20 @@ impl MyApp {
 //这是什么字体
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        //files changed by others
+        self.file_watcher.watch_work_dir(self.store.note_space.work_dir());
+        self.file_watcher.watch_files(&self.store.get_open_file_paths());
+        self.store.git_tick();
+        self.store.title_tick();
+        let changed_paths = self.file_watcher.poll();
+        if !changed_paths.is_empty() {
+            //canonical work dir is cached by watcher
+            if let Some(work_dir) = self.file_watcher.work_dir() {
+                for (curfile, mine, theirs) in self.store.reload_changed(work_dir, &changed_paths) {
+                    self.conflict_window.push(curfile, mine, theirs);
+                }
+            }
+        }
 
         egui::TopBottomPanel::top("top")
             .show_separator_line(true)
@@ -209,13 +281,76 @@ impl eframe::App for MyApp {
                 .resizable(true)
                 .default_height(360.0)
                 .show(ctx, |ui|{
-                let title = format!("Find result: match {} items", self.find_window.edit_ctx.line_num());
-                ui.add(ToolBar::new(&mut self.store, ToolBarType::WinBar(title)));
-                ui.add(crate::medit::Edit::new(&mut self.find_window.edit_ctx));
-                self.exe_find_edit_cmd();
+                if self.store.tool_bar_info.show_note_search {
+                    self.store.vault_search.poll();
+                    if self.store.vault_search.is_running() {
+                        ui.ctx().request_repaint();
+                    }
+                    let title = self.store.vault_search.title();
+                    ui.add(ToolBar::new(&mut self.store, ToolBarType::WinBar(title)));
+                    ui.add(crate::medit::Edit::new(&mut self.store.vault_search.edit_ctx));
+                    for cmd in self.store.vault_search.take_cmds() {
+                        self.store.execute_cmd(cmd);
+                    }
+                } else {
+                    let title = format!("Find result: match {} items", self.find_window.edit_ctx.line_num());
+                    ui.add(ToolBar::new(&mut self.store, ToolBarType::WinBar(title)));
+                    ui.add(crate::medit::Edit::new(&mut self.find_window.edit_ctx));
+                    self.exe_find_edit_cmd();
+                }
             });
         }
 
+        //backlinks panel
+        let mut show_backlinks = self.store.config.show_backlinks;
+        let cmds = self.backlinks.show(ctx, &self.store.note_space, &mut show_backlinks);
+        if show_backlinks != self.store.config.show_backlinks {
+            self.store.config_switch_show_backlinks();
+        }
+        for cmd in cmds {
+            self.store.execute_cmd(cmd);
+        }
+
+        //unlinked mentions
+        let mut show_mentions = self.store.config.show_mentions;
+        let cmds = self.mentions.show(ctx, &self.store.note_space, &mut show_mentions);
+        if show_mentions != self.store.config.show_mentions {
+            self.store.config_switch_show_mentions();
+        }
+        for cmd in cmds {
+            self.store.execute_cmd(cmd);
+        }
+
+        //graph view
+        let mut show_graph = self.store.config.show_graph;
+        let cmd = self.graph.show(ctx, &self.store.note_space, &mut show_graph);
+        if show_graph != self.store.config.show_graph {
+            self.store.config_switch_show_graph();
+        }
+        if let Some(cmd) = cmd {
+            self.store.execute_cmd(cmd);
+        }
+
+        //tag browser
+        let mut show_tags = self.store.config.show_tags;
+        let cmd = self.tags.show(ctx, &self.store.note_space, &mut show_tags);
+        if show_tags != self.store.config.show_tags {
+            self.store.config_switch_show_tags();
+        }
+        if let Some(cmd) = cmd {
+            self.store.execute_cmd(cmd);
+        }
+
+        //trash bin
+        let mut show_trash = self.store.config.show_trash;
+        let cmd = self.trash.show(ctx, self.store.note_space.trash(), &mut show_trash);
+        if show_trash != self.store.config.show_trash {
+            self.store.config_switch_show_trash();
+        }
+        if let Some(cmd) = cmd {
+            self.store.execute_cmd(cmd);
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             //test_clipboard(ui);
             //ui.image("file://E:/rustspace/medit/fonts/M.png");
@@ -233,16 +368,49 @@ impl eframe::App for MyApp {
             if self.store.note_space.rename_window_show(ui) {
                 let (org_name, new_name) = self.store.note_space.rename_from_to();
                 if org_name != new_name {
-                    let _ = self.store.rename_file(&org_name, &new_name);
+                    if self.store.note_space.rename_is_folder() {
+                        let _ = self.store.rename_folder(&org_name, &new_name);
+                    } else {
+                        let _ = self.store.rename_file(&org_name, &new_name);
+                    }
                 }
             }
 
+            //pick template of new note
+            if let Some((parent, folder, template)) = self.store.template_window.show(ui) {
+                let _ = self.store.create_note(parent, &folder, template);
+            }
+
+            //open workspace window
+            if let Some(work_dir) = self.store.workspace_window.show(ui) {
+                self.store.switch_workspace(&work_dir);
+            }
+
+            //conflict of changed file
+            if let Some((curfile, ConflictAction::TakeTheirs(text))) = self.conflict_window.show(ui) {
+                self.store.reload_ctx(&curfile, &text);
+            }
+
+            //versions of current note
+            self.store.history_window_show(ui);
+            self.store.export_window_show(ui);
+            self.store.import_window_show(ui);
+            self.store.git_window_show(ui);
+
+            //links changed by rename or delete
+            if let Some(note) = self.store.link_report.show(ui) {
+                let _ = self.store.open(&note);
+            }
+
             //find window as top window 
             if let Some(find) = self.find_window.show(ui) {
+                let is_find_in_notes = matches!(find.cmd, Some(medit::FindCmd::FindInNotes));
                 self.store.execute_cmd(medit::Command::FindReplace(find));
-                if let Some(edit_ctx) = self.store.cur_edit_ctx_mut() {
-                    let (find_cache, find_param) = edit_ctx.get_find_cache();
-                    self.find_window.set_find_result(find_cache, find_param);
+                if !is_find_in_notes {
+                    if let Some(edit_ctx) = self.store.cur_edit_ctx_mut() {
+                        let (find_cache, find_param) = edit_ctx.get_find_cache();
+                        self.find_window.set_find_result(find_cache, find_param);
+                    }
                 }
             }
 
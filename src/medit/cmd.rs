@@ -7,6 +7,7 @@ pub enum FindCmd {
     Replace,
     ReplaceAll,
     FindAll,
+    FindInNotes,
 }
 
 #[derive(Clone)]
@@ -53,12 +54,46 @@ impl FindReplaceCtx {
     }
 }
 
+/// a note dropped in the index tree
+#[derive(Clone, Debug)]
+pub struct LinkDrop {
+    pub note: String,
+    pub from: String,                       //parent in the tree, "" for none
+    pub to: String,                         //new parent, "" for none
+    pub sibling: Option<(String, bool)>,    //(sibling, is after), dropped between siblings
+    pub keep: bool,                         //keep the link in old parent
+}
+
+/// plain text in a note which mentions another note
+#[derive(Clone, Debug)]
+pub struct Mention {
+    pub note: String,       //note with the text
+    pub offset: usize,      //byte offset of text in the file
+    pub text: String,
+    pub target: String,     //the mentioned note
+}
+
 pub enum Command {
     OpenFile(String),
+    OpenHeading(String, String),    //(note, heading)
     PathList(String),
     DeleteFile(String),
+    RestoreTrash(String),       //id in trash
+    PurgeTrash(Option<String>), //id in trash, None for all
     NewFile(Option<String>),    //Option<parent>
+    NewFileInFolder(String),    //folder
+    NewFolder(Option<String>),  //Option<parent folder>
     RenameFile(String),   
+    RenameFolder(String),
+    DeleteFolder(String),
+    MoveFile(String, String),   //(note, to folder)
+    MoveFolder(String, String), //(folder, to folder)
+    DropLink(LinkDrop),         //move [[link]] between parents by drag and drop
+    LinkMention(Mention),       //wrap the mention in [[link]]
+    ExportSite(String),         //root note of html pages, "." for all
+    ImportVault,                //from obsidian or logseq
+    CopyIdLink(String),         //copy [[id:...]] of note, the id is added if none
+    FilterTag(Option<String>),  //show notes with the tag in index tree
     FixedFile(String),   
     UnFixedFile(String),   
     FindReplace(FindReplaceCtx),   
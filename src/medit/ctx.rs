@@ -2,12 +2,16 @@ use core::f32;
 use std::ops::Add;
 
 use crate::sitter::highlight_lines;
-use crate::medit::{ImageInfo, LinkInfo, PghType, CharRect, Cursor, MarkDownImpl, SegmentType, PghView, DoItem, DoCmd, DoMngr, Command, FindReplaceCtx};
+use super::items::PghEmbed;
+use crate::medit::{FrontMatter, ImageInfo, LinkInfo, ID_LINK_PREFIX, PghType, CharRect, Cursor, MarkDownImpl, SegmentType, PghView, DoItem, DoCmd, DoMngr, Command, FindReplaceCtx};
 use eframe::egui::{Color32, NumExt, Pos2, Rect, Sense, Ui};
 use eframe::egui::epaint::text::LayoutJob;
 use regex::Regex;
 use arboard::Clipboard;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
 #[derive(Clone, PartialEq)]
@@ -26,6 +30,7 @@ pub struct State {
     is_pointer_gone: bool,
 
     content_change_tick: u64,
+    edit_version: u64,      //increased on every change, not reset by save
     ime_area_changed: bool,
 }
 
@@ -44,6 +49,7 @@ impl Default for State {
             selecting: false,
             is_pointer_gone: false,
             content_change_tick: 0,
+            edit_version: 0,
             ime_area_changed: false,
         }
     }
@@ -92,6 +98,9 @@ pub struct EditColors {
     pub link_color: Color32,
     pub weak_color: Color32,
 }
+/// id in frontmatter -> note name, shared with the editors to show and open "[[id:...]]"
+pub type NoteIds = Rc<RefCell<HashMap<String, String>>>;
+
 pub struct EditCfg {
     pub font_size: f32,
     pub font_heigh: f32,
@@ -100,9 +109,12 @@ pub struct EditCfg {
     pub show_line_no: bool,
     pub is_markdown: bool,
     pub image_path: Option<String>,     //save image in markdown
+    pub note_path: Option<String>,      //dir of notes, for "![[note]]" embeds
+    pub note_ids: Option<NoteIds>,
     pub lang: Option<String>,
     pub need_line_click_cmd: bool,
     pub hightlight_seleted_word: bool,
+    pub read_only: bool,                //only move cursor, select and copy
 
     pub dark_color: EditColors,
     pub light_color: EditColors,
@@ -118,9 +130,12 @@ impl EditCfg {
             show_line_no: false,
             is_markdown,
             image_path,
+            note_path: None,
+            note_ids: None,
             lang: None,
             need_line_click_cmd: false,
             hightlight_seleted_word: true,
+            read_only: false,
 
             dark_color: EditColors {
                 text_color: Color32::from_rgb(192,192,192),
@@ -146,6 +161,14 @@ impl EditCfg {
         }
     }
 
+    /// note name of link target, None if the id isn't found
+    pub fn resolve_link(&self, target: &str) -> Option<String> {
+        match target.strip_prefix(ID_LINK_PREFIX) {
+            Some(id) => self.note_ids.as_ref()?.borrow().get(id.trim()).cloned(),
+            None => Some(target.to_string()),
+        }
+    }
+
     pub fn text_color(&self) -> Color32 {
         self.colors().text_color
     }
@@ -177,6 +200,27 @@ pub struct Ctx {
     same_cache: FindCache,
     clipboard: Clipboard,
     cfg: EditCfg,
+    front_matter: Option<FrontMatter>,  //yaml block of markdown, edited as properties
+    embeds: HashMap<(String, Option<String>), EmbedCache>,
+}
+
+/// the modified time of embedded note is checked in this interval
+const EMBED_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// lines of embedded note, laid out again when the file is modified
+struct EmbedCache {
+    modified: Option<SystemTime>,
+    checked: Instant,
+    font_size: f32,
+    dark_mode: bool,
+    jobs: Option<Vec<LayoutJob>>,
+}
+
+impl EmbedCache {
+    /// the jobs are laid out with the font and colors of cfg
+    fn is_same_cfg(&self, cfg: &EditCfg) -> bool {
+        self.font_size == cfg.font_size && self.dark_mode == cfg.dark_mode
+    }
 }
 
 impl Ctx {
@@ -195,7 +239,17 @@ impl Ctx {
             find_param: FindReplaceCtx::new(),
             same_cache: FindCache::new(),
             clipboard: Clipboard::new().unwrap(),   //todo: unwrap unsafe
-            cfg: EditCfg::new(font_size, is_markdown, image_path)
+            cfg: EditCfg::new(font_size, is_markdown, image_path),
+            front_matter: None,
+            embeds: HashMap::new(),
+        };
+
+        let text = if is_markdown {
+            let (front_matter, body) = FrontMatter::split(text);
+            ctx.front_matter = front_matter;
+            body
+        } else {
+            text
         };
 
         let markdown_impl = MarkDownImpl::new(
@@ -636,7 +690,10 @@ impl Ctx {
     }
 
     pub fn get_all_text(&self) -> String {
-        let mut s = "".to_string();
+        let mut s = match &self.front_matter {
+            Some(front_matter) => front_matter.to_text(),
+            None => "".to_string(),
+        };
         let mut pre_pgh_type = PghType::UnKnown;
         for (line_no, pgh_view) in self.pgh_views.iter().enumerate() {
             let cursor1: Cursor = 0.into();
@@ -939,6 +996,7 @@ impl Ctx {
 
     fn content_change_state(&mut self) {
         self.state.content_change_tick += 1;
+        self.state.edit_version += 1;
 
         //clean same cache
         self.flash_same_cache_with_seleted();
@@ -957,10 +1015,33 @@ impl Ctx {
         }
     }
 
+    pub fn front_matter(&self) -> Option<&FrontMatter> {
+        self.front_matter.as_ref()
+    }
+
+    /// call front_matter_changed after edited
+    pub fn front_matter_mut(&mut self) -> Option<&mut FrontMatter> {
+        self.front_matter.as_mut()
+    }
+
+    pub fn set_front_matter(&mut self, front_matter: Option<FrontMatter>) {
+        self.front_matter = front_matter;
+        self.front_matter_changed();
+    }
+
+    pub fn front_matter_changed(&mut self) {
+        self.state.content_change_tick += 1;
+        self.state.edit_version += 1;
+    }
+
     pub fn clean_change_tick(&mut self) {
         self.state.content_change_tick = 0;
     }
 
+    pub fn edit_version(&self) -> u64 {
+        self.state.edit_version
+    }
+
     pub fn is_content_changed(&self) -> bool {
         self.state.content_change_tick != 0
     }
@@ -1063,6 +1144,47 @@ impl Ctx {
         }
     }
 
+    /// add lines to the end, not in undo list, eg: results of a running search
+    pub fn append_lines(&mut self, text: &str) {
+        let markdown_impl = MarkDownImpl::new(text, self.cfg.is_markdown, None, false, self.cfg());
+        let mut new_views = markdown_impl.markdown_to_pgh_texts();
+        self.pgh_views.append(&mut new_views);
+    }
+
+    /// replace the whole text and the frontmatter as one edit, it can be undone
+    pub fn replace_all_text(&mut self, text: &str) {
+        //undo list is applied in reverse order
+        let mut undo_cmd = DoCmd::new();
+        let mut redo_cmd = DoCmd::new();
+        let text = if self.cfg.is_markdown {
+            let (front_matter, body) = FrontMatter::split(text);
+            undo_cmd.push_front_matter(self.front_matter.clone());
+            redo_cmd.push_front_matter(front_matter.clone());
+            self.front_matter = front_matter;
+            body
+        } else {
+            text
+        };
+        let markdown_impl = MarkDownImpl::new(text, self.cfg.is_markdown, None, false, self.cfg());
+        let new_views = markdown_impl.markdown_to_pgh_texts();
+
+        for line_no in (0..self.pgh_views.len()).rev() {
+            undo_cmd.push_insert(line_no, self.get_line_clone(line_no));
+            redo_cmd.push_delete(line_no);
+        }
+        for (line_no, pgh_view) in new_views.iter().enumerate() {
+            undo_cmd.push_delete(line_no);
+            redo_cmd.push_insert(line_no, Some(pgh_view.clone()));
+        }
+        undo_cmd.set_cursor(self.cursor2());
+
+        self.pgh_views = new_views;
+        self.set_cursor2(0.into());
+        self.set_cursor1_reset();
+        self.push_do(undo_cmd, redo_cmd);
+        self.on_content_change();
+    }
+
     pub fn enter_auto_pak_ctrl(left: &str) -> String {
         let re = Regex::new(r"^-[ \t]+\[.*\] ").unwrap();
         if re.is_match(left) {
@@ -1430,9 +1552,49 @@ impl Ctx {
         self.cmd_list.pop()    
     }
 
+    /// lines of embedded note or its heading section, None if not found
+    pub fn embed_jobs(&mut self, note: &str, heading: Option<&str>) -> Option<Vec<LayoutJob>> {
+        let name = self.cfg.resolve_link(note)?;
+        //only the notes in the dir of notes
+        if name.split(['/', '\\']).any(|c| c == "..") {
+            return None;
+        }
+        let path = format!("{}/{}.md", self.cfg.note_path.as_ref()?, name);
+        let key = (note.to_string(), heading.map(|h| h.to_string()));
+        if let Some(cache) = self.embeds.get(&key).filter(|cache| cache.is_same_cfg(&self.cfg)) {
+            if cache.checked.elapsed() < EMBED_CHECK_INTERVAL {
+                return cache.jobs.clone();
+            }
+        }
+        let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        if let Some(cache) = self.embeds.get_mut(&key).filter(|cache| cache.is_same_cfg(&self.cfg)) {
+            cache.checked = Instant::now();
+            if cache.modified == modified {
+                return cache.jobs.clone();
+            }
+        }
+        let jobs = std::fs::read_to_string(&path).ok()
+            .and_then(|text| MarkDownImpl::note_section(&text, heading))
+            .map(|section| PghEmbed::section_jobs(&section, &self.cfg));
+        self.embeds.insert(key, EmbedCache {
+            modified,
+            checked: Instant::now(),
+            font_size: self.cfg.font_size,
+            dark_mode: self.cfg.dark_mode,
+            jobs: jobs.clone(),
+        });
+        jobs
+    }
+
     pub fn insert_link_click_command(&mut self, link_info: LinkInfo) {
         match link_info {
-            LinkInfo::File(file) => self.insert_cmd(Command::OpenFile(file)),
+            LinkInfo::File(file, heading) | LinkInfo::Embed(file, heading) => {
+                let file = self.cfg.resolve_link(&file).unwrap_or(file);
+                match heading {
+                    Some(heading) => self.insert_cmd(Command::OpenHeading(file, heading)),
+                    None => self.insert_cmd(Command::OpenFile(file)),
+                }
+            }
             LinkInfo::Link(url) => self.insert_cmd(Command::OpenUrl(url)),
             LinkInfo::Image(image) => {
                 println!("todo: flash image: {:?}", image)
@@ -1471,6 +1633,7 @@ impl Ctx {
                 DoItem::Insert(x) => self.line_change_tick(x.line),
                 DoItem::Delete(x) => self.line_change_tick(x.line),
                 DoItem::Update(x) => self.line_change_tick(x.line),
+                DoItem::FrontMatter(_) => {}
             }
         }
 
@@ -1503,6 +1666,9 @@ impl Ctx {
                 self.line_change_tick(do_line.line);
                 println!("Update {} => {}", do_line.line, (do_line.pgh_view).clone().unwrap().get_text())
             }
+            DoItem::FrontMatter(front_matter) => {
+                self.front_matter = front_matter.clone();
+            }
         }
     }
 
@@ -1588,7 +1754,7 @@ impl Ctx {
         start_is_boundary && end_is_boundary
     }
 
-    fn find_func(s: &str, param: &FindReplaceCtx) -> Vec<std::ops::Range<usize>> {
+    pub fn find_func(s: &str, param: &FindReplaceCtx) -> Vec<std::ops::Range<usize>> {
         if param.is_reg {
             if let Some(re) = &param.regex {
                 re.find_iter(s)
@@ -1711,6 +1877,12 @@ impl Ctx {
     pub fn flash_same_cache_with_param(&mut self, param: &FindReplaceCtx) {
         self.same_cache = self.find_all_func(param, false, 0, self.pgh_views.len());
     }
+
+    /// highlight the words found from line, the lines before are kept
+    pub fn append_same_cache_with_param(&mut self, param: &FindReplaceCtx, from_line: usize) {
+        let mut found = self.find_all_func(param, false, from_line, self.pgh_views.len());
+        self.same_cache.cache.append(&mut found.cache);
+    }
 }
 
 
@@ -0,0 +1,315 @@
+/// one "key: value" of frontmatter
+#[derive(Clone, Debug)]
+pub struct Property {
+    pub key: String,
+    pub value: String,      //items of list are joined by ", "
+    pub edited: bool,       //need write back from key and value
+    is_list: bool,
+    is_inline_list: bool,   //"[a, b]" or lines of "- a"
+    read_only: bool,        //block text of "|" or ">", or nested keys, only the raw lines are written back
+    raw: Vec<String>,       //org lines, written back when not edited
+}
+
+impl Property {
+    pub fn new(key: &str, value: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            value: value.to_string(),
+            edited: true,
+            is_list: false,
+            is_inline_list: false,
+            read_only: false,
+            raw: vec![],
+        }
+    }
+
+    fn clean_item(s: &str) -> String {
+        let s = s.trim();
+        let s = s.strip_prefix('"').and_then(|x| x.strip_suffix('"'))
+            .or(s.strip_prefix('\'').and_then(|x| x.strip_suffix('\'')))
+            .unwrap_or(s);
+        s.to_string()
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        if line.starts_with([' ', '\t', '#', '-']) {
+            return None;
+        }
+        let (key, value) = line.split_once(':')?;
+        let value = value.trim();
+        let mut prop = Self {
+            key: key.trim().to_string(),
+            value: Self::clean_item(value),
+            edited: false,
+            is_list: false,
+            is_inline_list: false,
+            read_only: false,
+            raw: vec![line.to_string()],
+        };
+        if value.starts_with(['|', '>']) {
+            prop.read_only = true;
+            prop.value = String::new();
+        } else if let Some(items) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            prop.is_list = true;
+            prop.is_inline_list = true;
+            prop.value = items.split(',')
+                .map(Self::clean_item)
+                .filter(|x| !x.is_empty())
+                .collect::<Vec<_>>()
+                .join(", ");
+        }
+        Some(prop)
+    }
+
+    //lines after "key:"
+    fn push_line(&mut self, line: &str) {
+        self.raw.push(line.to_string());
+        //value is the text joined for showing
+        if self.read_only {
+            if line.trim().is_empty() {
+                return;
+            }
+            if !self.value.is_empty() {
+                self.value += " ";
+            }
+            self.value += line.trim();
+            return;
+        }
+        if let Some(item) = line.trim().strip_prefix("- ") {
+            if !self.is_list {
+                self.is_list = true;
+                self.value = String::new();
+            }
+            if !self.value.is_empty() {
+                self.value += ", ";
+            }
+            self.value += &Self::clean_item(item);
+        } else if !line.trim().is_empty() && !line.trim_start().starts_with('#') {
+            //nested keys can't be edited as one value
+            self.read_only = true;
+            self.value = self.raw[1..].iter().map(|l| l.trim()).filter(|l| !l.is_empty()).collect::<Vec<_>>().join(" ");
+        }
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// items of a list, or the value split by ','
+    pub fn items(&self) -> Vec<String> {
+        self.value.split(',')
+            .map(Self::clean_item)
+            .filter(|x| !x.is_empty())
+            .collect()
+    }
+
+    fn quote(value: &str) -> String {
+        let need_quote = value.contains(": ") || value.contains(" #") || value.ends_with(':')
+            || value.starts_with(['#', '[', ']', '{', '}', '&', '*', '!', '|', '>', '\'', '"', '%', '@', '`', ',']);
+        if need_quote {
+            format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+        } else {
+            value.to_string()
+        }
+    }
+
+    fn to_lines(&self) -> Vec<String> {
+        if !self.edited {
+            return self.raw.clone();
+        }
+        if self.key.trim().is_empty() {
+            return vec![];
+        }
+        if self.is_list && !self.is_inline_list {
+            let mut lines = vec![format!("{}:", self.key)];
+            for item in self.items() {
+                lines.push(format!("  - {}", Self::quote(&item)));
+            }
+            lines
+        } else if self.is_list {
+            let items: Vec<String> = self.items().iter().map(|x| Self::quote(x)).collect();
+            vec![format!("{}: [{}]", self.key, items.join(", "))]
+        } else if self.value.is_empty() {
+            vec![format!("{}:", self.key)]
+        } else {
+            vec![format!("{}: {}", self.key, Self::quote(&self.value))]
+        }
+    }
+}
+
+/// yaml block at the start of a note, between two lines of "---"
+/// the org text is kept, so it's written back without any change if not edited
+#[derive(Clone, Debug)]
+pub struct FrontMatter {
+    pub props: Vec<Property>,
+    raw: String,
+    head: Vec<String>,      //lines before the first key, eg: comments
+    close: String,          //"---" or "..."
+    eol: String,
+    gap: String,            //empty lines after the block
+    changed: bool,
+}
+
+impl FrontMatter {
+    pub fn new() -> Self {
+        Self {
+            props: vec![],
+            raw: String::new(),
+            head: vec![],
+            close: "---".to_string(),
+            eol: "\n".to_string(),
+            gap: "\n".to_string(),
+            changed: true,
+        }
+    }
+
+    /// split the text to frontmatter and body
+    pub fn split(text: &str) -> (Option<FrontMatter>, &str) {
+        let mut lines = text.split_inclusive('\n');
+        let Some(first) = lines.next() else {
+            return (None, text);
+        };
+        if first.trim_end() != "---" || !first.ends_with('\n') {
+            return (None, text);
+        }
+        let eol = if first.ends_with("\r\n") {"\r\n"} else {"\n"};
+
+        let mut front = FrontMatter::new();
+        front.eol = eol.to_string();
+        front.changed = false;
+        let mut end = first.len();
+        let mut closed = false;
+        for line in lines.by_ref() {
+            end += line.len();
+            let content = line.trim_end_matches(['\r', '\n']);
+            if content.trim_end() == "---" || content.trim_end() == "..." {
+                front.close = content.to_string();
+                closed = true;
+                break;
+            }
+            if let Some(prop) = Property::parse(content) {
+                front.props.push(prop);
+            } else if let Some(prop) = front.props.last_mut() {
+                prop.push_line(content);
+            } else {
+                front.head.push(content.to_string());
+            }
+        }
+        if !closed {
+            return (None, text);
+        }
+
+        //empty lines after the block
+        let gap_len = text[end..].len() - text[end..].trim_start_matches(['\r', '\n']).len();
+        front.gap = text[end..end + gap_len].to_string();
+        end += gap_len;
+        front.raw = text[..end].to_string();
+        (Some(front), &text[end..])
+    }
+
+    pub fn parse(text: &str) -> Option<FrontMatter> {
+        Self::split(text).0
+    }
+
+    /// text of the block with the empty lines after it
+    pub fn to_text(&self) -> String {
+        if !self.is_changed() {
+            return self.raw.clone();
+        }
+        let props: Vec<String> = self.props.iter().flat_map(|prop| prop.to_lines()).collect();
+        //a new block without any key isn't written
+        if self.raw.is_empty() && self.head.is_empty() && props.is_empty() {
+            return String::new();
+        }
+        let mut lines = vec!["---".to_string()];
+        lines.extend(self.head.iter().cloned());
+        lines.extend(props);
+        lines.push(self.close.clone());
+        let gap = if self.gap.is_empty() {self.eol.clone()} else {self.gap.clone()};
+        lines.join(&self.eol) + &self.eol + &gap
+    }
+
+    pub fn is_changed(&self) -> bool {
+        self.changed || self.props.iter().any(|p| p.edited)
+    }
+
+    pub fn set_changed(&mut self) {
+        self.changed = true;
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Property> {
+        self.props.iter().find(|p| p.key == key)
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) {
+        if let Some(prop) = self.props.iter_mut().find(|p| p.key == key) {
+            prop.value = value.to_string();
+            prop.edited = true;
+            prop.read_only = false;
+        } else {
+            self.props.push(Property::new(key, value));
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.props.len() {
+            self.props.remove(index);
+            self.changed = true;
+        }
+    }
+
+    /// items of "tags" or "tag", without "#"
+    pub fn tags(&self) -> Vec<String> {
+        self.props.iter()
+            .filter(|p| p.key == "tags" || p.key == "tag")
+            .flat_map(|p| {
+                if p.is_list {
+                    p.items()
+                } else {
+                    p.value.split([',', ' ']).map(|x| x.trim().to_string()).collect()
+                }
+            })
+            .map(|x| x.trim_start_matches('#').to_string())
+            .filter(|x| !x.is_empty())
+            .collect()
+    }
+
+    /// (key, value) of all properties
+    pub fn pairs(&self) -> Vec<(String, String)> {
+        self.props.iter().map(|p| (p.key.clone(), p.value.clone())).collect()
+    }
+}
+
+#[test]
+pub fn test_front_matter() {
+    let text = "---\r\n# comment\r\ntitle: \"Hello: world\"\r\ntags:\r\n  - a\r\n  - 'b/c'\r\naliases: [x, y]\r\n---\r\n\r\n# Title\r\n";
+    let (front, body) = FrontMatter::split(text);
+    let mut front = front.unwrap();
+    assert_eq!(body, "# Title\r\n");
+    assert_eq!(front.to_text() + body, text);
+    assert_eq!(front.get("title").unwrap().value, "Hello: world");
+    assert_eq!(front.tags(), vec!["a", "b/c"]);
+    assert_eq!(front.get("aliases").unwrap().items(), vec!["x", "y"]);
+
+    //only the edited property is rewritten
+    front.set("aliases", "x, z");
+    front.set("new", "#v");
+    assert_eq!(front.to_text(), "---\r\n# comment\r\ntitle: \"Hello: world\"\r\ntags:\r\n  - a\r\n  - 'b/c'\r\naliases: [x, z]\r\nnew: \"#v\"\r\n---\r\n\r\n");
+
+    //block text is kept when other properties are edited
+    let text = "---\nnote: |\n  line 1\n\n  line 2\ntitle: a\n---\n";
+    let mut front = FrontMatter::parse(text).unwrap();
+    assert!(front.get("note").unwrap().is_read_only());
+    assert_eq!(front.get("note").unwrap().value, "line 1 line 2");
+    front.set("title", "b");
+    assert_eq!(front.to_text(), "---\nnote: |\n  line 1\n\n  line 2\ntitle: b\n---\n\n");
+
+    let mut front = FrontMatter::new();
+    front.props.push(Property::new("", ""));
+    assert_eq!(front.to_text(), "");
+    front.props[0].key = "k".to_string();
+    assert_eq!(front.to_text(), "---\nk:\n---\n\n");
+
+    assert!(FrontMatter::parse("---\nnot closed\n").is_none());
+    assert!(FrontMatter::parse("# no front\n---\n").is_none());
+}
@@ -1,5 +1,7 @@
-use crate::medit::{icon, ImageInfo, CharRect, Ctx, Cursor, PghItem};
-use eframe::egui::{vec2, Image, Pos2, Rect, Response, Ui, Vec2};
+use super::ctx::EditCfg;
+use crate::medit::{icon, ImageInfo, CharRect, Ctx, Cursor, LinkInfo, MarkDownImpl, PghItem, PghType, PghView, SegmentType};
+use eframe::egui::{vec2, FontId, Frame, Image, Label, Pos2, Rect, Response, RichText, Sense, Stroke, Ui, Vec2};
+use eframe::egui::epaint::text::{LayoutJob, TextFormat};
 use regex::Regex;
 
 const SPACE_X: f32 = 8.0;
@@ -580,3 +582,200 @@ impl PghItem for PghImage {
     }
 }
 
+
+#[derive(Clone)]
+pub struct PghEmbed {
+    char_rect: Option<Vec<CharRect>>,
+    note: String,
+    heading: Option<String>,
+}
+
+impl PghEmbed {
+    pub fn new(note: String, heading: Option<String>) -> Self {
+        PghEmbed {
+            char_rect: None,
+            note,
+            heading,
+        }
+    }
+
+    /// lines of embedded note, the section is split to lines as in editor, rendered but not editable
+    pub fn section_jobs(section: &str, cfg: &EditCfg) -> Vec<LayoutJob> {
+        MarkDownImpl::new_simple(section, cfg).markdown_to_pgh_texts().iter()
+            .map(|pghview| Self::line_job(pghview, cfg))
+            .collect()
+    }
+
+    fn line_job(line: &PghView, cfg: &EditCfg) -> LayoutJob {
+        //code and table are shown as their text
+        if matches!(line.pgh_type, PghType::Code | PghType::Table) {
+            let mut format = TextFormat::simple(FontId::monospace(cfg.font_size), cfg.text_color());
+            format.background = cfg.code_bg_color();
+            return LayoutJob::single_section(line.get_text(), format);
+        }
+        let pghview = MarkDownImpl::new_simple(&line.get_text(), cfg).markdown_to_pghview();
+        let mut job = LayoutJob::default();
+        for seg in pghview.pgh.iter().filter(|seg| seg.seg_type == SegmentType::Text) {
+            match seg.item.layout_job() {
+                Some(seg_job) => {
+                    for section in seg_job.sections {
+                        job.append(&seg_job.text[section.byte_range.clone()], section.leading_space, section.format);
+                    }
+                }
+                None => {
+                    let format = TextFormat::simple(FontId::proportional(cfg.font_size), cfg.text_color());
+                    job.append(&seg.item.text(), 0.0, format);
+                }
+            }
+        }
+        job
+    }
+
+    pub fn layout_paragraph(
+        ui: &mut Ui,
+        ctx: &mut Ctx,
+        line_no: usize,
+        segment: usize,
+        pgh_text: &dyn PghItem,
+    ) -> Response {
+        let (note, heading) = pgh_text.embed_info().unwrap();
+        //laid out again only if the source is modified
+        let jobs = ctx.embed_jobs(&note, heading.as_deref());
+
+        let width = ui.available_width() * 0.95;
+        let mut jump = false;
+        let response = Frame::group(ui.style())
+            .stroke(Stroke::new(1.0, ctx.cfg().weak_color()))
+            .show(ui, |ui| {
+                ui.set_width(width);
+                ui.vertical(|ui| {
+                    ui.horizontal(|ui| {
+                        match &heading {
+                            Some(heading) => ui.weak(format!("{} # {}", note, heading)),
+                            None => ui.weak(&note),
+                        };
+                        if ui.small_button("open").on_hover_text("Jump to the source note").clicked() {
+                            jump = true;
+                        }
+                    });
+                    match jobs {
+                        Some(jobs) => {
+                            for mut job in jobs {
+                                job.wrap.max_width = width;
+                                ui.label(job);
+                            }
+                        }
+                        None => {
+                            ui.weak("Note not found");
+                        }
+                    }
+                });
+            })
+            .response;
+
+        if jump {
+            ctx.insert_link_click_command(LinkInfo::Embed(note, heading));
+        }
+
+        //update rect info
+        ctx.update_view(
+            line_no,
+            segment,
+            response.rect,
+            item_char_rect(&response.rect),
+        );
+
+        response
+    }
+}
+
+impl PghItem for PghEmbed {
+    fn update_view_info(&mut self, char_rect: Vec<CharRect>) {
+        self.char_rect = Some(char_rect);
+    }
+
+    fn pos_from_cursor(&self, cursor: &Cursor) -> Option<Rect> {
+        pos_from_cursor(&self.char_rect, cursor)
+    }
+
+    fn cursor_from_pos(&self, line_no: usize, segment: usize, pos: &Pos2) -> Option<Cursor> {
+        cursor_from_pos(&self.char_rect, line_no, segment, pos)
+    }
+
+    fn embed_info(&self) -> Option<(String, Option<String>)> {
+        Some((self.note.clone(), self.heading.clone()))
+    }
+}
+
+/// current title of the note linked by "[[id:...]]"
+#[derive(Clone)]
+pub struct PghTitle {
+    char_rect: Option<Vec<CharRect>>,
+    link_info: LinkInfo,
+}
+
+impl PghTitle {
+    pub fn new(link_info: LinkInfo) -> Self {
+        PghTitle {
+            char_rect: None,
+            link_info,
+        }
+    }
+
+    pub fn layout_paragraph(
+        ui: &mut Ui,
+        ctx: &mut Ctx,
+        line_no: usize,
+        segment: usize,
+        pgh_text: &dyn PghItem,
+    ) -> Response {
+        let target = match pgh_text.link_info() {
+            Some(LinkInfo::File(target, _)) => target,
+            _ => String::new(),
+        };
+        let text = match ctx.cfg().resolve_link(&target) {
+            Some(name) => RichText::new(name.rsplit('/').next().unwrap_or(&name)).color(ctx.cfg().link_color()),
+            None => RichText::new("missing note").color(ctx.cfg().weak_color()),
+        };
+        let response = ui.add(Label::new(text.size(ctx.font_size())).sense(Sense::click()))
+            .on_hover_text(target);
+
+        //update rect info
+        ctx.update_view(
+            line_no,
+            segment,
+            response.rect,
+            item_char_rect(&response.rect),
+        );
+
+        response
+    }
+}
+
+impl PghItem for PghTitle {
+    fn update_view_info(&mut self, char_rect: Vec<CharRect>) {
+        self.char_rect = Some(char_rect);
+    }
+
+    fn pos_from_cursor(&self, cursor: &Cursor) -> Option<Rect> {
+        pos_from_cursor(&self.char_rect, cursor)
+    }
+
+    fn cursor_from_pos(&self, line_no: usize, segment: usize, pos: &Pos2) -> Option<Cursor> {
+        cursor_from_pos(&self.char_rect, line_no, segment, pos)
+    }
+
+    fn link_info(&self) -> Option<LinkInfo> {
+        Some(self.link_info.clone())
+    }
+}
+
+#[test]
+pub fn test_embed_section_jobs() {
+    let cfg = EditCfg::new(17.0, true, None);
+    let jobs = PghEmbed::section_jobs("- a\n  b\n- c\n\n```\nx\n```\n", &cfg);
+    let texts: Vec<&str> = jobs.iter().map(|job| job.text.as_str()).collect();
+    assert_eq!(texts.len(), 3);
+    assert!(texts[0].contains('a') && texts[0].contains('b'));
+    assert_eq!(texts[2], "x");
+}
@@ -620,6 +620,21 @@ impl Edit<'_> {
 
     fn on_event(ui: &mut Ui, ctx: &mut Ctx, event: &Event) {
         Self::on_mouse_event(ui, ctx, event);
+        if ctx.cfg().read_only {
+            match event {
+                Event::Key { key: Key::Backspace | Key::Delete, .. } => {}
+                Event::Key { key: Key::A, modifiers, .. } if modifiers.ctrl => {
+                    Self::on_text_event(ui, ctx, event);
+                }
+                Event::Copy => {
+                    Self::on_text_event(ui, ctx, event);
+                }
+                _ => {
+                    Self::on_key_event(ui, ctx, event);
+                }
+            }
+            return;
+        }
         Self::on_key_event(ui, ctx, event);
         Self::on_ime_event(ui, ctx, event);
         Self::on_text_event(ui, ctx, event); 
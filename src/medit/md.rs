@@ -10,29 +10,96 @@ use markdown::unist::Position;
 use regex::Regex;
 
 use super::ctx::EditCfg;
+use super::FrontMatter;
+
+/// gfm with frontmatter, the yaml block at the start isn't parsed as markdown
+pub fn parse_options() -> markdown::ParseOptions {
+    markdown::ParseOptions {
+        constructs: markdown::Constructs {
+            frontmatter: true,
+            ..markdown::Constructs::gfm()
+        },
+        ..markdown::ParseOptions::gfm()
+    }
+}
+
+/// target of "[[id:...]]", the note is found by the id in its frontmatter
+pub const ID_LINK_PREFIX: &str = "id:";
+
+/// "[[target#anchor|alias]]", anchor and alias are optional
+#[derive(Debug, Clone, PartialEq)]
+pub struct WikiLink {
+    pub range: Range<usize>,    //of "[[...]]" in text, "!" included if embed
+    pub target: String,
+    pub anchor: Option<String>,
+    pub alias: Option<String>,
+    pub embed: bool,            //"![[...]]", content of target is shown inline
+}
+
+impl WikiLink {
+    /// id of "[[id:...]]" link
+    pub fn id(&self) -> Option<&str> {
+        self.target.strip_prefix(ID_LINK_PREFIX)
+    }
+
+    /// inner is the text between "[[" and "]]"
+    pub fn parse(inner: &str, range: Range<usize>) -> Self {
+        let (link, alias) = match inner.split_once('|') {
+            Some((link, alias)) => (link, Some(alias.to_string())),
+            None => (inner, None),
+        };
+        let (target, anchor) = match link.split_once('#') {
+            Some((target, anchor)) => (target, Some(anchor.to_string())),
+            None => (link, None),
+        };
+        Self {
+            range,
+            target: target.trim().to_string(),
+            anchor,
+            alias,
+            embed: false,
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut s = format!("{}[[{}", if self.embed {"!"} else {""}, self.target);
+        if let Some(anchor) = &self.anchor {
+            s += &format!("#{}", anchor);
+        }
+        if let Some(alias) = &self.alias {
+            s += &format!("|{}", alias);
+        }
+        s + "]]"
+    }
+}
 
 #[derive(Clone)]
 pub enum LinkInfo {
-    File(String),   //file
+    File(String, Option<String>),   //(file, heading)
     Link(String),   //url
-    Image(ImageInfo)
+    Image(ImageInfo),
+    Embed(String, Option<String>),  //(note, heading) of "![[...]]"
 }
 
 #[derive(Clone)]
 pub struct LinkEnd {
     end_pos: usize,
     link_info: LinkInfo,
+    show_title: bool,   //"[[id:...]]" without alias, title of the note is shown
 }
 
 impl LinkEnd {
-    pub fn new_file(end_pos: usize, file: String) -> Self {
-        LinkEnd { end_pos, link_info: LinkInfo::File(file) }
+    pub fn new_file(end_pos: usize, file: String, heading: Option<String>) -> Self {
+        LinkEnd { end_pos, link_info: LinkInfo::File(file, heading), show_title: false }
     }
     pub fn new_link(end_pos: usize, url: String) -> Self {
-        LinkEnd { end_pos, link_info: LinkInfo::Link(url) }
+        LinkEnd { end_pos, link_info: LinkInfo::Link(url), show_title: false }
+    }
+    pub fn new_embed(end_pos: usize, note: String, heading: Option<String>) -> Self {
+        LinkEnd { end_pos, link_info: LinkInfo::Embed(note, heading), show_title: false }
     }
     pub fn new_image(end_pos: usize, alt: String, url: String) -> Self {
-        LinkEnd { end_pos, link_info: LinkInfo::Image(ImageInfo{alt, url, img:None}) }
+        LinkEnd { end_pos, link_info: LinkInfo::Image(ImageInfo{alt, url, img:None}), show_title: false }
     }
 }
 
@@ -150,6 +217,11 @@ impl<'a> MarkDownImpl<'a> {
         format.color = self.cfg.link_color();
     }
 
+    fn format_tag(&self, format: &mut TextFormat) {
+        format.color = self.cfg.link_color();
+        format.background = self.cfg.code_bg_color();
+    }
+
     fn text_between_pos(&self, p1: Option<&Position>, p2: Option<&Position>) -> String {
         if let Some(pos1) = p1 {
             if let Some(pos2) = p2 {
@@ -193,36 +265,94 @@ impl<'a> MarkDownImpl<'a> {
         }
     }
 
+    /// ranges of "#tag" or "#nested/tag" in text
+    pub fn text_tags(text: &str) -> Vec<Range<usize>> {
+        let re = Regex::new(r"#([\w\-]+(?:/[\w\-]+)*)").unwrap();
+        re.captures_iter(text)
+            .filter_map(|cap| {
+                let all = cap.get(0)?;
+                let tag = cap.get(1)?.as_str();
+                //"#" is at start or after a space, and tag isn't a number
+                let is_start = text[..all.start()].chars().last().map_or(true, |c| c.is_whitespace());
+                if is_start && tag.chars().any(|c| !c.is_ascii_digit()) {
+                    Some(all.range())
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// tags in "tags:" of frontmatter, eg: "tags: [a, b]", "tags: a, b" or a list of "- a"
+    pub fn frontmatter_tags(text: &str) -> Vec<String> {
+        FrontMatter::parse(text).map(|front| front.tags()).unwrap_or_default()
+    }
+
     fn text_check_double_link(&self, node: &Node, job: &mut LayoutJob, link_ends: &mut Vec<LinkEnd>, format: &mut TextFormat) {
         if let Node::Text(text) = node {
             let info = self.text_double_links(node);
-            if info.is_empty() {
+            //tags out of links
+            let tags: Vec<Range<usize>> = Self::text_tags(&text.value).into_iter()
+                .filter(|tag| !info.iter().any(|x| tag.start < x.1 && x.0 < tag.end))
+                .collect();
+            if info.is_empty() && tags.is_empty() {
                 job.append(&text.value, 0.0, format.clone());
             } else {
                 let mut pre = 0 as usize;
+                let append_tags = |job: &mut LayoutJob, from: usize, to: usize| {
+                    let mut pre = from;
+                    for tag in tags.iter().filter(|t| t.start >= from && t.end <= to) {
+                        if tag.start > pre {
+                            job.append(&text.value[pre..tag.start], 0.0, format.clone());
+                        }
+                        let mut tag_format = format.clone();
+                        self.format_tag(&mut tag_format);
+                        job.append(&text.value[tag.clone()], 0.0, tag_format);
+                        pre = tag.end;
+                    }
+                    if to > pre {
+                        job.append(&text.value[pre..to], 0.0, format.clone());
+                    }
+                };
                 for x in info {
-                    if x.0 > pre {
-                        let value = &text.value[pre..x.0];
-                        job.append(&value, 0.0, format.clone());
+                    //"![[...]]" embeds the note
+                    let embed = x.0 > pre && text.value[..x.0].ends_with('!');
+                    let start = if embed {x.0 - 1} else {x.0};
+                    if start > pre {
+                        append_tags(job, pre, start);
+                    }
+
+                    let inner = &text.value[x.0+2..x.1-2];
+                    let link = WikiLink::parse(inner, x.0..x.1);
+                    //"[[name|" is hidden too, only the alias is shown
+                    let mut alias_start = inner.find('|').map_or(0, |i| i + 1);
+                    //"[[id:...]]" is hidden, the title of note is shown after it
+                    let show_title = link.alias.is_none() && link.id().is_some();
+                    if show_title {
+                        alias_start = inner.len();
                     }
 
                     let pos = node.position().unwrap();
-                    let range_left = pos.start.offset + x.0 .. pos.start.offset + x.0 + 2;
+                    let range_left = pos.start.offset + start .. pos.start.offset + x.0 + 2 + alias_start;
                     let range_right = pos.start.offset + x.1 - 2 .. pos.start.offset + x.1;
-                    job.append("[[", 0.0, self.format_hide(&range_left, &range_right));
+                    job.append(&text.value[start..x.0+2+alias_start], 0.0, self.format_hide(&range_left, &range_right));
 
-                    let value = &text.value[x.0+2..x.1-2];
                     let mut link_format = format.clone();
                     self.format_link(&mut link_format);
-                    job.append(value, 0.0, link_format);
+                    job.append(&inner[alias_start..], 0.0, link_format);
 
                     job.append("]]", 0.0, self.format_hide(&range_left, &range_right));
-                    link_ends.push(LinkEnd::new_file(job.sections.len(), value.to_string()));
+                    if embed {
+                        link_ends.push(LinkEnd::new_embed(job.sections.len(), link.target, link.anchor));
+                    } else {
+                        let mut link_end = LinkEnd::new_file(job.sections.len(), link.target, link.anchor);
+                        link_end.show_title = show_title;
+                        link_ends.push(link_end);
+                    }
                     pre = x.1;
                 }
-                let value = &text.value[pre..];
-                if value.len() > 0 {
-                    job.append(&value, 0.0, format.clone());
+                if text.value.len() > pre {
+                    append_tags(job, pre, text.value.len());
                 }
             }
         }
@@ -361,6 +491,11 @@ impl<'a> MarkDownImpl<'a> {
                 if let Some(link) = link_ends.iter().find(|end| end.end_pos == i+1) {
                     pghview.push_text(seg_str, Some(sub_job));
 
+                    //push title of "[[id:...]]"
+                    if link.show_title {
+                        pghview.push_title(link.link_info.clone());
+                    }
+
                     //push link pgh_segment
                     pghview.push_icon(icon::IconName::icon_external_link(link.link_info.clone()));
                     sub_job = LayoutJob::default();
@@ -371,6 +506,11 @@ impl<'a> MarkDownImpl<'a> {
                         pghview.push_image(image_info.to_owned());
                     }
 
+                    //push embed pgh_segment
+                    if let LinkInfo::Embed(note, heading) = &link.link_info {
+                        pghview.push_embed(note.clone(), heading.clone());
+                    }
+
                     //the last segment, push a empty text segment after the icon-button
                     if i+1 == job.sections.len() {
                         pghview.push_text("".to_string(), None);
@@ -537,7 +677,7 @@ impl<'a> MarkDownImpl<'a> {
 
     pub fn markdown_to_pghview(&self) -> PghView {
         if self.enable_markdown {
-            if let Ok(ast) = markdown::to_mdast(&self.text, &markdown::ParseOptions::gfm()) {
+            if let Ok(ast) = markdown::to_mdast(&self.text, &parse_options()) {
                 if let Some(items) = ast.children() {
                     if let Some(item) = items.first() {
                         //todo, only get first now
@@ -630,7 +770,7 @@ impl<'a> MarkDownImpl<'a> {
         let mut pghvews = vec![];
         if self.enable_markdown {
             //println!("{}");
-            if let Ok(ast) = markdown::to_mdast(&self.text, &markdown::ParseOptions::gfm()) {
+            if let Ok(ast) = markdown::to_mdast(&self.text, &parse_options()) {
                 if let Some(items) = ast.children() {
                     for item in items {
                         self.node_to_pgh_text(item, &mut pghvews);
@@ -662,7 +802,7 @@ impl<'a> MarkDownImpl<'a> {
         match node {
             Node::Text(p) => {
                 for (start, end) in self.text_double_links(node) {
-                    links.push(p.value[start+2..end-2].to_string());
+                    links.push(WikiLink::parse(&p.value[start+2..end-2], start..end).target);
                 }
             }
             Node::Link(link) => {
@@ -681,11 +821,224 @@ impl<'a> MarkDownImpl<'a> {
 
     pub fn markdown_get_links(&self) -> Vec<String> {
         let mut links:Vec<String> = vec![];
-        if let Ok(ast) = markdown::to_mdast(&self.text, &markdown::ParseOptions::gfm()) {
+        if let Ok(ast) = markdown::to_mdast(&self.text, &parse_options()) {
             self.get_node_links(&ast, &mut links);
         }
         links
     }
+
+    fn get_node_wiki_links(text: &str, node: &Node, links: &mut Vec<WikiLink>) {
+        match node {
+            Node::Text(_) => {
+                //match in the org text, the value of node may be unescaped
+                let Some(pos) = node.position() else {
+                    return;
+                };
+                let re = Regex::new(r"!?\[\[([^\[\]]+?)\]\]").unwrap();
+                for cap in re.captures_iter(&text[pos.start.offset..pos.end.offset]) {
+                    let all = cap.get(0).unwrap();
+                    let range = pos.start.offset + all.start()..pos.start.offset + all.end();
+                    let mut link = WikiLink::parse(&cap[1], range);
+                    link.embed = all.as_str().starts_with('!');
+                    links.push(link);
+                }
+            }
+            _ => {
+                if let Some(items) = node.children() {
+                    for item in items {
+                        Self::get_node_wiki_links(text, item, links);
+                    }
+                }
+            }
+        }
+    }
+
+    fn get_node_text_ranges(node: &Node, ranges: &mut Vec<Range<usize>>) {
+        match node {
+            Node::Text(_) => {
+                if let Some(pos) = node.position() {
+                    ranges.push(pos.start.offset..pos.end.offset);
+                }
+            }
+            //text and url of markdown links
+            Node::Link(_) | Node::LinkReference(_) => {}
+            _ => {
+                if let Some(items) = node.children() {
+                    for item in items {
+                        Self::get_node_text_ranges(item, ranges);
+                    }
+                }
+            }
+        }
+    }
+
+    /// ranges of plain text, code, frontmatter, images and markdown links are skipped
+    pub fn text_ranges(text: &str) -> Vec<Range<usize>> {
+        let mut ranges = vec![];
+        if let Ok(ast) = markdown::to_mdast(text, &parse_options()) {
+            Self::get_node_text_ranges(&ast, &mut ranges);
+        }
+        ranges
+    }
+
+    /// [[links]] of text, the links in code are skipped
+    pub fn wiki_links(text: &str) -> Vec<WikiLink> {
+        let mut links = vec![];
+        if let Ok(ast) = markdown::to_mdast(text, &parse_options()) {
+            Self::get_node_wiki_links(text, &ast, &mut links);
+        }
+        links
+    }
+
+    /// links to target get the new target, anchor and alias are kept
+    /// the links are removed if new_target is None, with the line if nothing else in it
+    /// return the new text and count of changed links
+    pub fn rewrite_links(text: &str, target: &str, new_target: Option<&str>) -> (String, usize) {
+        let Some(new_target) = new_target else {
+            let (new_text, removed) = Self::remove_links(text, &[target.to_string()]);
+            return (new_text, removed.len());
+        };
+        let links: Vec<WikiLink> = Self::wiki_links(text).into_iter()
+            .filter(|link| link.target == target)
+            .collect();
+        let mut new_text = text.to_string();
+        for link in links.iter().rev() {
+            let new_link = WikiLink { target: new_target.to_string(), ..link.clone() };
+            new_text.replace_range(link.range.clone(), &new_link.to_text());
+        }
+        (new_text, links.len())
+    }
+
+    /// remove the links to any of targets, with the line if nothing else in it
+    /// return the new text and the removed (offset, text), the offsets are in org text and ascending,
+    /// so inserting them back in order gets the org text
+    pub fn remove_links(text: &str, targets: &[String]) -> (String, Vec<(usize, String)>) {
+        let links: Vec<WikiLink> = Self::wiki_links(text).into_iter()
+            .filter(|link| targets.contains(&link.target))
+            .collect();
+        let mut new_text = text.to_string();
+        let mut removed = vec![];
+        for link in links.iter().rev() {
+            let line_start = new_text[..link.range.start].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = new_text[link.range.end..].find('\n').map_or(new_text.len(), |i| link.range.end + i + 1);
+            let range = if new_text[line_start..line_end].trim() == &new_text[link.range.clone()] {
+                line_start..line_end
+            } else {
+                link.range.clone()
+            };
+            removed.push((range.start, new_text[range.clone()].to_string()));
+            new_text.replace_range(range, "");
+        }
+        removed.reverse();
+        (new_text, removed)
+    }
+
+    /// level of heading if the line is "# heading", case is ignored
+    pub fn match_heading(line: &str, heading: &str) -> Option<usize> {
+        let level = line.chars().take_while(|c| *c == '#').count();
+        if level == 0 || !line[level..].starts_with(' ') || !line[level..].trim().eq_ignore_ascii_case(heading.trim()) {
+            return None;
+        }
+        Some(level)
+    }
+
+    /// body of note without frontmatter, or the section of heading until the next heading of same or upper level
+    pub fn note_section(text: &str, heading: Option<&str>) -> Option<String> {
+        let (_, body) = FrontMatter::split(text);
+        let Some(heading) = heading else {
+            return Some(body.to_string());
+        };
+        let mut lines = body.lines();
+        let level = lines.by_ref().find_map(|line| Self::match_heading(line, heading))?;
+        let mut section = vec![];
+        for line in lines {
+            let next = line.chars().take_while(|c| *c == '#').count();
+            if next > 0 && next <= level && line[next..].starts_with(' ') {
+                break;
+            }
+            section.push(line);
+        }
+        Some(section.join("\n"))
+    }
+
+    /// text of the first heading after frontmatter, "#" in code blocks is skipped
+    pub fn first_heading(text: &str) -> Option<String> {
+        let (_, body) = FrontMatter::split(text);
+        let mut in_code = false;
+        for line in body.lines() {
+            if line.trim_start().starts_with("```") {
+                in_code = !in_code;
+                continue;
+            }
+            let level = line.chars().take_while(|c| *c == '#').count();
+            if in_code || level == 0 || level > 6 || !line[level..].starts_with(' ') {
+                continue;
+            }
+            let heading = line[level..].trim().trim_end_matches('#').trim();
+            if !heading.is_empty() {
+                return Some(heading.to_string());
+            }
+        }
+        None
+    }
+
+    fn get_node_tags(&self, node: &Node, tags: &mut Vec<String>) {
+        match node {
+            Node::Text(p) => {
+                let links = self.text_double_links(node);
+                for tag in Self::text_tags(&p.value) {
+                    if !links.iter().any(|x| tag.start < x.1 && x.0 < tag.end) {
+                        tags.push(p.value[tag.start+1..tag.end].to_string());
+                    }
+                }
+            }
+            _ => {
+                if let Some(items) = node.children() {
+                    for item in items {
+                        self.get_node_tags(item, tags);
+                    }
+                }
+            }
+        }
+    }
+
+    /// inline tags and tags of frontmatter, without "#"
+    pub fn markdown_get_tags(&self) -> Vec<String> {
+        let mut tags = Self::frontmatter_tags(&self.text);
+        if let Ok(ast) = markdown::to_mdast(&self.text, &parse_options()) {
+            self.get_node_tags(&ast, &mut tags);
+        }
+        let mut uniq = vec![];
+        for tag in tags {
+            if !uniq.contains(&tag) {
+                uniq.push(tag);
+            }
+        }
+        uniq
+    }
+
+    fn get_node_headings(node: &Node, headings: &mut Vec<String>) {
+        match node {
+            Node::Heading(_) => {
+                headings.push(node.to_string());
+            }
+            _ => {
+                if let Some(items) = node.children() {
+                    for item in items {
+                        Self::get_node_headings(item, headings);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn markdown_get_headings(&self) -> Vec<String> {
+        let mut headings = vec![];
+        if let Ok(ast) = markdown::to_mdast(&self.text, &parse_options()) {
+            Self::get_node_headings(&ast, &mut headings);
+        }
+        headings
+    }
 }
 
 fn ast_type(ast: &Node) -> &str {
@@ -751,3 +1104,64 @@ pub fn test_md() {
 
     println!("{:?}", ast);
 }
+
+#[test]
+pub fn test_md_tags() {
+    let cfg = EditCfg::new(17.0, true, None);
+    let md = "---\ntags: [a, \"b\"]\n---\ntext #tag and #nested/tag, not a#b or #123, [[x#y]]\n";
+    let markdown = MarkDownImpl::new_simple(md, &cfg);
+    assert_eq!(markdown.markdown_get_tags(), vec!["a", "b", "tag", "nested/tag"]);
+
+    let md = "---\ntitle: t\ntags:\n  - one\n  - two/three\n---\n";
+    assert_eq!(MarkDownImpl::frontmatter_tags(md), vec!["one", "two/three"]);
+}
+
+#[test]
+pub fn test_md_rewrite_links() {
+    let md = "# Title\n[[a]]\ntext [[a|alias]] and [[a#Sec]], [[ab]]\n`[[a]]`\n```\n[[a]]\n```\n";
+    let links = MarkDownImpl::wiki_links(md);
+    assert_eq!(links.len(), 4);
+    assert_eq!(links[1], WikiLink { range: 19..30, target: "a".to_string(), anchor: None, alias: Some("alias".to_string()), embed: false });
+
+    let (text, count) = MarkDownImpl::rewrite_links(md, "a", Some("b/c"));
+    assert_eq!(count, 3);
+    assert_eq!(text, "# Title\n[[b/c]]\ntext [[b/c|alias]] and [[b/c#Sec]], [[ab]]\n`[[a]]`\n```\n[[a]]\n```\n");
+
+    let (text, count) = MarkDownImpl::rewrite_links("x\r\n[[a]]\r\ny [[a]]", "a", None);
+    assert_eq!(count, 2);
+    assert_eq!(text, "x\r\ny ");
+
+    let org = "x\n[[a|alias]]\ny [[id:1#Sec]] [[b]]\n";
+    let (text, removed) = MarkDownImpl::remove_links(org, &["a".to_string(), "id:1".to_string()]);
+    assert_eq!(text, "x\ny  [[b]]\n");
+    assert_eq!(removed, vec![(2, "[[a|alias]]\n".to_string()), (16, "[[id:1#Sec]]".to_string())]);
+}
+
+#[test]
+pub fn test_md_link_alias() {
+    let cfg = EditCfg::new(17.0, true, None);
+    let md = "[[a|Alias]] [[b#Heading]] [[c#Heading|Alias]]\n";
+    let markdown = MarkDownImpl::new_simple(md, &cfg);
+    assert_eq!(markdown.markdown_get_links(), vec!["a", "b", "c"]);
+}
+
+#[test]
+pub fn test_md_embed() {
+    let md = "---\ntitle: t\n---\nintro\n# One\na\n## Sub\nb\n# Two\nc";
+    assert_eq!(MarkDownImpl::note_section(md, None).unwrap(), "intro\n# One\na\n## Sub\nb\n# Two\nc");
+    assert_eq!(MarkDownImpl::note_section(md, Some("one")).unwrap(), "a\n## Sub\nb");
+    assert_eq!(MarkDownImpl::note_section(md, Some("Sub")).unwrap(), "b");
+    assert!(MarkDownImpl::note_section(md, Some("none")).is_none());
+
+    let (text, count) = MarkDownImpl::rewrite_links("x ![[a#One]]\n![[a]]\n", "a", Some("b"));
+    assert_eq!(count, 2);
+    assert_eq!(text, "x ![[b#One]]\n![[b]]\n");
+    let (text, _) = MarkDownImpl::rewrite_links("x\n![[a]]\n", "a", None);
+    assert_eq!(text, "x\n");
+}
+
+#[test]
+pub fn test_first_heading() {
+    assert_eq!(MarkDownImpl::first_heading("---\n# c: x\n---\ntext\n```\n# code\n```\n#tag\n## My Plan ##\n# Two"), Some("My Plan".to_string()));
+    assert_eq!(MarkDownImpl::first_heading("text\n# \n"), None);
+}
@@ -15,15 +15,17 @@ pub mod undo;
 pub mod cmd;
 pub mod cursor;
 pub mod image;
+pub mod front;
 
 pub use ctx::Ctx;
 pub use items::PghCheckBox;
 pub use layout::Edit;
-pub use md::{LinkInfo, MarkDownImpl};
+pub use md::{LinkInfo, MarkDownImpl, WikiLink, ID_LINK_PREFIX};
 pub use cursor::Cursor;
 pub use pgh::{CharRect, PghItem, SegmentType, PghType, PghView, TableInfo};
 pub use text::PghText;
 pub use undo::{DoItem, DoCmd, DoMngr};
 pub use icon::IconName;
-pub use cmd::{FindCmd, FindReplaceCtx, Command};
+pub use cmd::{FindCmd, FindReplaceCtx, Command, LinkDrop, Mention};
 pub use image::ImageInfo;
+pub use front::FrontMatter;
@@ -7,8 +7,8 @@ use eframe::egui::{
 };
 
 use crate::sitter::{LightSlice, highlight_lines, support_lang};
-use crate::medit::{icon, Cursor, Ctx, DoCmd, MarkDownImpl, PghCheckBox, PghText};
-use super::items::{PghBreak, PghHead, PghIcon, PghImage, PghIndent, PghPoint, PghQuoteIndent};
+use crate::medit::{icon, Cursor, Ctx, DoCmd, LinkInfo, MarkDownImpl, PghCheckBox, PghText};
+use super::items::{PghBreak, PghEmbed, PghTitle, PghHead, PghIcon, PghImage, PghIndent, PghPoint, PghQuoteIndent};
 use super::image::{ImageInfo};
 use super::IconName;
 
@@ -44,6 +44,8 @@ pub enum SegmentType {
     Break,
     Icon,
     Image,
+    Embed,
+    Title,
 }
 
 pub trait PghItem: DynClone {
@@ -96,6 +98,15 @@ pub trait PghItem: DynClone {
     fn image_info(&self) -> Option<ImageInfo> {
         None
     }
+
+    /// (note, heading) of "![[...]]"
+    fn embed_info(&self) -> Option<(String, Option<String>)> {
+        None
+    }
+
+    fn link_info(&self) -> Option<LinkInfo> {
+        None
+    }
 }
 
 impl Clone for Box<dyn PghItem> {
@@ -306,6 +317,16 @@ impl PghView {
             .push(PghSegment::new(SegmentType::Image, Box::new(PghImage::new(image_info))));
     }
 
+    pub fn push_title(&mut self, link_info: LinkInfo) {
+        self.pgh
+            .push(PghSegment::new(SegmentType::Title, Box::new(PghTitle::new(link_info))));
+    }
+
+    pub fn push_embed(&mut self, note: String, heading: Option<String>) {
+        self.pgh
+            .push(PghSegment::new(SegmentType::Embed, Box::new(PghEmbed::new(note, heading))));
+    }
+
     pub fn is_pos_in(&self, pos: &Pos2) -> bool {
         if let Some(rect) = self.rect {
             if pos.x >= rect.left_top().x
@@ -877,18 +898,30 @@ impl PghView {
                             }
                         }
                     }
-                    SegmentType::Image => {
+                    SegmentType::Title => {
+                        let r = PghTitle::layout_paragraph(ui, ctx, line_no, segment, pgh_segment.item.as_ref());
+                        if r.clicked() {
+                            if let Some(link_info) = pgh_segment.item.link_info() {
+                                ctx.insert_link_click_command(link_info);
+                            }
+                        }
+                    }
+                    SegmentType::Image | SegmentType::Embed => {
                         images.push((segment,pgh_segment));
                     }
                 }
             }
         });
 
-        //draw images
+        //draw images and embeds
         for (segment, image) in images {
             ui.horizontal(|ui| {
                 PghIndent::layout_paragraph(ui, ctx, line_no, segment);
-                PghImage::layout_paragraph(ui, ctx, line_no, segment, &image.item);
+                if image.seg_type == SegmentType::Embed {
+                    PghEmbed::layout_paragraph(ui, ctx, line_no, segment, image.item.as_ref());
+                } else {
+                    PghImage::layout_paragraph(ui, ctx, line_no, segment, &image.item);
+                }
             });
         }
 
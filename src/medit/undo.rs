@@ -1,4 +1,4 @@
-use crate::medit::{Cursor, PghView};
+use crate::medit::{Cursor, FrontMatter, PghView};
 
 #[derive(Clone, Debug)]
 pub struct DoLine {
@@ -11,6 +11,7 @@ pub enum DoItem {
     Insert(DoLine),
     Delete(DoLine),
     Update(DoLine),
+    FrontMatter(Option<FrontMatter>),
 }
 
 #[derive(Clone, Debug)]
@@ -48,6 +49,10 @@ impl DoCmd {
         self.items.push(item);
     }
 
+    pub fn push_front_matter(&mut self, front_matter: Option<FrontMatter>) {
+        self.items.push(DoItem::FrontMatter(front_matter));
+    }
+
     pub fn set_cursor(&mut self, cursor: Cursor) {
         self.cursor = cursor;
     }
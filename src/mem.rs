@@ -1,9 +1,25 @@
 use serde::{Serialize, Deserialize};
 use crate::sitter;
-use crate::space::{CurFile, NoteSpace};
-use crate::medit::{Command, Ctx, FindCmd};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use crate::space::{CurFile, NoteSpace, TreeSort};
+use crate::workspace::{Workspaces, WorkspaceWin};
+use crate::search::VaultSearch;
+use crate::watch::FileWatcher;
+use crate::periodic::{Period, PeriodicNotes};
+use crate::template::{self, TemplateVars, TemplateWin, TEMPLATE_FOLDER};
+use crate::trash::ParentLink;
+use crate::history::HistoryWin;
+use crate::export::ExportWin;
+use crate::mentions::locate_mention;
+use crate::import::ImportWin;
+use crate::git::{GitCfg, GitCommitter, GitWin};
+use crate::links::{self, LinkReport};
+use chrono::NaiveDate;
+use eframe::egui::Ui;
+use crate::medit::{Command, Ctx, FindCmd, FrontMatter, LinkDrop, MarkDownImpl, Mention, ID_LINK_PREFIX};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
 use std::usize;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -17,7 +33,23 @@ pub struct Config {
     pub fixed_files: Vec<String>,
     pub opend_files: Vec<String>,
     pub tree_open_state: HashMap<String, bool>,
-    pub tree_open_state_changed: bool
+    pub tree_open_state_changed: bool,
+    #[serde(default)]
+    pub show_backlinks: bool,
+    #[serde(default)]
+    pub show_graph: bool,
+    #[serde(default)]
+    pub show_tags: bool,
+    #[serde(default)]
+    pub periodic: PeriodicNotes,
+    #[serde(default)]
+    pub show_trash: bool,
+    #[serde(default)]
+    pub git: GitCfg,
+    #[serde(default)]
+    pub show_mentions: bool,
+    #[serde(default)]
+    pub tree_sort: TreeSort,
 }
 
 impl Config {
@@ -32,7 +64,15 @@ impl Config {
             fixed_files: vec![],
             opend_files: vec![],
             tree_open_state: HashMap::new(),
-            tree_open_state_changed: false
+            tree_open_state_changed: false,
+            show_backlinks: false,
+            show_graph: false,
+            show_tags: false,
+            periodic: PeriodicNotes::default(),
+            show_trash: false,
+            git: GitCfg::default(),
+            show_mentions: false,
+            tree_sort: TreeSort::Manual,
         }
     }
 
@@ -58,6 +98,8 @@ impl Config {
 pub struct ToolBarInfo {
     pub width: Option<f32>,
     pub is_show_bottom: bool,
+    pub show_note_search: bool,     //bottom panel shows results of find in notes
+    pub link_path: Vec<String>,     //path of current note in path bar, chosen when it has several parents
 }
 
 impl ToolBarInfo {
@@ -65,6 +107,8 @@ impl ToolBarInfo {
         Self {
             width: None,
             is_show_bottom: false,
+            show_note_search: false,
+            link_path: vec![],
         }
     }
 }
@@ -74,19 +118,93 @@ pub struct Store {
     pub ectx_map: HashMap<CurFile, Ctx>,
     pub note_space: NoteSpace,
     pub tool_bar_info: ToolBarInfo,
+    pub workspaces: Workspaces,
+    pub workspace_window: WorkspaceWin,
+    pub vault_search: VaultSearch,
+    pub template_window: TemplateWin,
+    history_window: HistoryWin,
+    export_window: ExportWin,
+    import_window: ImportWin,
+    git_window: GitWin,
+    pub link_report: LinkReport,
+    git_commit_time: Instant,
+    git_committer: GitCommitter,
+    pub git_error: Option<String>,  //error of the last commit
+    new_notes: HashSet<String>,     //untitled notes created in this session, renamed by the first heading
+    title_checked: (String, u64, usize),    //note, edit version and cursor line of the last title check
 }
 
 impl Store {
-    pub fn default() -> Self {
+    pub fn new(workspaces: Workspaces, work_dir: PathBuf) -> Self {
         let mut store = Self {
             ectx_map: HashMap::new(),
-            note_space: NoteSpace::new(),
+            note_space: NoteSpace::new(work_dir),
             config: Config::default(),
             tool_bar_info: ToolBarInfo::default(),
+            workspaces,
+            workspace_window: WorkspaceWin::default(),
+            vault_search: VaultSearch::new(),
+            template_window: TemplateWin::default(),
+            history_window: HistoryWin::default(),
+            export_window: ExportWin::default(),
+            import_window: ImportWin::default(),
+            git_window: GitWin::default(),
+            link_report: LinkReport::default(),
+            git_commit_time: Instant::now(),
+            git_committer: GitCommitter::default(),
+            git_error: None,
+            new_notes: HashSet::new(),
+            title_checked: (String::new(), 0, 0),
         };
+        store.workspace_add_recent();
         store.config_restore();
         store
     }
+
+    fn workspace_add_recent(&mut self) {
+        self.workspaces.add_recent(self.note_space.work_dir());
+        self.workspaces.save();
+    }
+
+    pub fn current_workspace(&self) -> String {
+        Workspaces::abs_path(self.note_space.work_dir())
+    }
+
+    /// close all files of current workspace, and open the new one
+    pub fn switch_workspace(&mut self, work_dir: &str) {
+        let work_dir = PathBuf::from(work_dir);
+        if Workspaces::abs_path(&work_dir) == self.current_workspace() {
+            return;
+        }
+
+        //save current note before leave, the workspace is kept if any buffer can't be saved
+        let _ = self.save();
+        if self.save_changed().is_err() {
+            return;
+        }
+        self.config_save();
+
+        self.ectx_map = HashMap::new();
+        self.new_notes.clear();
+        self.title_checked = (String::new(), 0, 0);
+        //views of the old workspace
+        self.vault_search.stop();
+        self.vault_search = VaultSearch::new();
+        self.template_window = TemplateWin::default();
+        self.history_window = HistoryWin::default();
+        self.export_window = ExportWin::default();
+        self.import_window = ImportWin::default();
+        self.git_window = GitWin::default();
+        self.link_report = LinkReport::default();
+        //a running commit finishes in the old repo, its result is dropped
+        self.git_committer = GitCommitter::default();
+        self.git_commit_time = Instant::now();
+        self.git_error = None;
+        self.note_space = NoteSpace::new(work_dir);
+        self.config = Config::default();
+        self.workspace_add_recent();
+        self.config_restore();
+    }
     
     pub fn cur_edit_ctx_mut(&mut self) -> Option<&mut Ctx> {
         if let Some(curfile) = self.note_space.get_current_cur() {
@@ -143,7 +261,7 @@ impl Store {
 
         // insert new ctx
         let text = self.note_space.read_note(name)?;
-        let new_ctx = Ctx::new(&text, true, Some(self.note_space.image_path()));
+        let new_ctx = self.new_note_ctx(&text);
         self.ectx_map.insert(curfile.clone(), new_ctx);
 
         // set ctx
@@ -180,14 +298,27 @@ impl Store {
         }
         if name == "." {
             return Ok(String::new())
-        } else if name.contains("/") || name.contains("\\") {
+        } else if self.note_space.is_note_name(name) {
+            self.open_note(name)
+        } else {
             let new_name = name.replace("\\", "/");
             self.open_file(&new_name)
-        } else {
-            self.open_note(name)
         }
     }
 
+    /// open the note and move the cursor to the heading
+    pub fn open_heading(&mut self, name: &str, heading: &str) -> std::io::Result<()> {
+        self.open(name)?;
+        let Some(ctx) = self.cur_edit_ctx_mut() else {
+            return Ok(());
+        };
+        let line = (0..ctx.line_num()).find(|&i| MarkDownImpl::match_heading(&ctx.get_line_text(i), heading).is_some());
+        if let Some(line) = line {
+            self.execute_goto(format!("{}", line + 1));
+        }
+        Ok(())
+    }
+
     pub fn close(&mut self, file: &CurFile) {
         println!("close {:?}", file);
         if self.ectx_map.len() > 1 {
@@ -206,56 +337,482 @@ impl Store {
         }
     }
 
+    fn write_ctx(note_space: &NoteSpace, curfile: &CurFile, ctx: &mut Ctx) -> std::io::Result<()> {
+        let text = ctx.get_all_text();
+        if curfile.is_file() {
+            note_space.write_file(&curfile.path(), &text)?;
+        } else {
+            note_space.write_note(&curfile.name(), &text)?;
+            let _ = note_space.history().snapshot(&curfile.name(), &text);
+        }
+        ctx.clean_change_tick();
+        Ok(())
+    }
+
+    /// write all changed buffers, the opened files which aren't notes too
+    fn save_changed(&mut self) -> std::io::Result<()> {
+        for (curfile, ctx) in self.ectx_map.iter_mut() {
+            if ctx.is_content_changed() {
+                Self::write_ctx(&self.note_space, curfile, ctx)?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn save(&mut self) -> std::io::Result<()> {
         if let Some(curfile) = self.note_space.get_current_cur() {
             if let Some(ctx) = self.ectx_map.get_mut(&curfile) {
-                let text = ctx.get_all_text();
-                if curfile.is_file() {
-                    self.note_space.write_file(&curfile.path(), &text)?;
-                } else {
-                    self.note_space.write_note(&curfile.name(), &text)?;
-                }
-                ctx.clean_change_tick();
+                Self::write_ctx(&self.note_space, &curfile, ctx)?;
             }
         }
+        if self.config.git.auto_commit && self.config.git.commit_on_save && self.is_auto_commit_repo() {
+            let _ = self.git_commit();
+        }
+        Ok(())
+    }
+
+    /// rename the new note by its first heading when the cursor leaves the heading, called in every frame
+    pub fn title_tick(&mut self) {
+        let Some(cur) = self.note_space.get_current_note() else {
+            return;
+        };
+        if !self.new_notes.contains(&cur) {
+            return;
+        }
+        let Some(curfile) = self.note_space.get_current_cur() else {
+            return;
+        };
+        let Some(ctx) = self.ectx_map.get(&curfile) else {
+            return;
+        };
+        //only check again after an edit or the cursor moved to another line
+        let checked = (cur.clone(), ctx.edit_version(), ctx.cursor2().line_no);
+        if self.title_checked == checked {
+            return;
+        }
+        self.title_checked = checked;
+        let text = ctx.get_all_text();
+        let Some(heading) = MarkDownImpl::first_heading(&text) else {
+            return;
+        };
+        //still typing the heading
+        if MarkDownImpl::match_heading(&ctx.get_line_text(ctx.cursor2().line_no), &heading).is_some() {
+            return;
+        }
+        let name = template::title_name(&heading);
+        if name.is_empty() {
+            return;
+        }
+        let base = NoteSpace::join_name(&NoteSpace::folder_of(&cur), &name);
+        //name_N is given when base is used by another note
+        let is_same = cur == base || cur.strip_prefix(&format!("{}_", base))
+            .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+        if is_same {
+            return;
+        }
+        let new_name = self.note_space.unique_name(&base);
+        //not renamed by title any more, the name is kept
+        if self.rename_new_note(&cur, &new_name).is_err() {
+            self.new_notes.remove(&cur);
+        }
+    }
+
+    /// rename current note and the links in parents, the editor is kept
+    fn rename_new_note(&mut self, org_name: &str, new_name: &str) -> std::io::Result<()> {
+        self.save()?;
+        self.note_space.rename(org_name, new_name)?;
+        let _ = self.note_space.history().rename(org_name, new_name);
+        self.link_report.start(&format!("rename {} to {}", org_name, new_name));
+        self.replace_links_in_parents(org_name, new_name, &HashMap::new())?;
+        self.link_report.hide();
+        self.rename_in_config(org_name, new_name);
+        self.new_notes.remove(org_name);
+        self.new_notes.insert(new_name.to_string());
+        self.note_space.flash_data();
+
+        //move the editor to new name, cursor and undo are kept
+        let Some(org_file) = self.note_space.get_current_cur() else {
+            return Ok(());
+        };
+        let new_file = self.note_space.note_name_to_curfile(new_name);
+        if let Some(ctx) = self.ectx_map.remove(&org_file) {
+            self.ectx_map.insert(new_file.clone(), ctx);
+        }
+        self.open_set_ctx(&new_file);
         Ok(())
     }
 
+    /// commit all changes of work dir in background, skipped if the last one is running
+    pub fn git_commit(&mut self) -> std::io::Result<()> {
+        self.git_commit_time = Instant::now();
+        if self.note_space.git().is_some() {
+            self.git_committer.start(self.note_space.work_dir());
+        }
+        Ok(())
+    }
+
+    /// a repository holding the work dir as a sub dir belongs to the user, it's not committed automatically
+    fn is_auto_commit_repo(&self) -> bool {
+        self.note_space.git().is_some_and(|repo| repo.is_own())
+    }
+
+    /// auto commit on interval, called in every frame
+    pub fn git_tick(&mut self) {
+        if let Some(result) = self.git_committer.poll() {
+            self.git_error = result.err().map(|e| e.to_string());
+        }
+        let cfg = &self.config.git;
+        if !cfg.auto_commit || cfg.commit_on_save || !self.is_auto_commit_repo() {
+            return;
+        }
+        if self.git_commit_time.elapsed() >= Duration::from_secs(cfg.interval_mins.max(1) * 60) {
+            let _ = self.git_commit();
+        }
+    }
+
+    pub fn git_init(&mut self) -> std::io::Result<()> {
+        self.note_space.git_init()?;
+        self.git_commit()
+    }
+
+    /// log and blame of current note
+    pub fn git_window_active(&mut self) {
+        if let (Some(cur), Some(repo)) = (self.note_space.get_current_note(), self.note_space.git()) {
+            self.git_window.active(&cur, repo);
+        }
+    }
+
+    pub fn git_window_show(&mut self, ui: &mut Ui) {
+        let image_path = self.note_space.image_path();
+        self.git_window.show(ui, self.note_space.git(), &image_path);
+    }
+
+    fn curfile_path(&self, curfile: &CurFile) -> PathBuf {
+        if curfile.is_file() {
+            PathBuf::from(curfile.path())
+        } else {
+            PathBuf::from(self.note_space.name2path(&curfile.name()))
+        }
+    }
+
+    /// paths of opened files which are not notes
+    pub fn get_open_file_paths(&self) -> Vec<String> {
+        self.ectx_map.keys()
+            .filter(|curfile| curfile.is_file())
+            .map(|curfile| curfile.path())
+            .collect()
+    }
+
+    /// files changed on disk by others, reload the clean ctx and refresh the tree
+    /// return (file, text in ctx, text on disk) of the changed ctx, work_dir is canonical
+    pub fn reload_changed(&mut self, work_dir: &Path, paths: &[PathBuf]) -> Vec<(CurFile, String, String)> {
+        let need_flash = paths.iter().any(|path| FileWatcher::is_note_change(work_dir, path));
+        if need_flash {
+            self.note_space.flash_data();
+        }
+
+        let mut conflicts = vec![];
+        let curfiles: Vec<CurFile> = self.ectx_map.keys().cloned().collect();
+        for curfile in curfiles {
+            let file_path = self.curfile_path(&curfile);
+            if !paths.iter().any(|path| FileWatcher::is_same_path(path, &file_path)) {
+                continue;
+            }
+            //deleted or renamed, keep the ctx
+            let Ok(text) = std::fs::read_to_string(&file_path) else {
+                continue;
+            };
+            let Some(ctx) = self.ectx_map.get(&curfile) else {
+                continue;
+            };
+            let mine = ctx.get_all_text();
+            if mine.replace("\r\n", "\n") == text.replace("\r\n", "\n") {
+                continue;
+            }
+            if ctx.is_content_changed() {
+                conflicts.push((curfile, mine, text));
+            } else {
+                self.reload_ctx(&curfile, &text);
+            }
+        }
+        conflicts
+    }
+
+    /// editor of note, embeds and id links are found in work dir
+    fn new_note_ctx(&self, text: &str) -> Ctx {
+        let mut ctx = Ctx::new(text, true, Some(self.note_space.image_path()));
+        ctx.cfg_mut().note_path = Some(self.note_space.work_dir().display().to_string());
+        ctx.cfg_mut().note_ids = Some(self.note_space.note_ids());
+        ctx
+    }
+
+    /// replace the text of ctx, keep the position of cursor
+    pub fn reload_ctx(&mut self, curfile: &CurFile, text: &str) {
+        let Some(old_ctx) = self.ectx_map.get(curfile) else {
+            return;
+        };
+        let line_no = old_ctx.cursor2().line_no;
+        let top_line = old_ctx.top_line();
+
+        let mut new_ctx = if curfile.is_note() {
+            self.new_note_ctx(text)
+        } else {
+            let mut new_ctx = Ctx::new(text, false, None);
+            if let Some(ext) = PathBuf::from(curfile.path()).extension(){
+                let ext = ext.to_string_lossy().to_string();
+                new_ctx.set_height_lang(sitter::ext_to_lang(&ext));
+            }
+            new_ctx
+        };
+        Self::set_edit_cfg(&self.config, &mut new_ctx);
+        let line_no = line_no.min(new_ctx.line_num().saturating_sub(1));
+        new_ctx.set_cursor2(line_no.into());
+        new_ctx.set_cursor1_reset();
+        new_ctx.set_top_line(top_line.min(line_no));
+        new_ctx.set_open_time();
+        self.ectx_map.insert(curfile.clone(), new_ctx);
+    }
+
     pub fn new_note(&mut self, parent: Option<String>) -> std::io::Result<()> {
-        if let Some(new_name) = self.note_space.new_file_name() {
-            //create new file
-            self.note_space.write_note(&new_name, "")?;
-
-            //add link to parent
-            if let Some(parent_name) = parent {
-                if Some(parent_name.to_string()) == self.note_space.get_current_note() {
-                    let _ = self.save();
-                } 
-                
-                let text = self.note_space.read_note(&parent_name)?;
-                let text = text + "\n\n[[" + &new_name + "]]";
-                self.note_space.write_note(&parent_name, &text)?;
-            }
-            //flash data
+        //new child note is created in the folder of parent
+        let folder = parent.as_ref().map_or(String::new(), |p| NoteSpace::folder_of(p));
+        self.new_note_at(parent, &folder)
+    }
+
+    /// pick a template firstly if there are templates
+    pub fn new_note_at(&mut self, parent: Option<String>, folder: &str) -> std::io::Result<()> {
+        let templates = self.note_space.get_notes_in_folder(TEMPLATE_FOLDER);
+        if templates.is_empty() {
+            self.create_note(parent, folder, None)
+        } else {
+            self.template_window.active(parent, folder, templates);
+            Ok(())
+        }
+    }
+
+    /// create a note from template, the name is from "filename" of template
+    /// or untitled_N, which is renamed by its first heading
+    pub fn create_note(&mut self, parent: Option<String>, folder: &str, template: Option<String>) -> std::io::Result<()> {
+        let template = match &template {
+            Some(name) => self.note_space.read_note(name)?,
+            None => String::new(),
+        };
+        let mut vars = TemplateVars::new(parent.as_ref());
+        //an empty name from the pattern is untitled too
+        let new_name = match template::filename_pattern(&template).and_then(|pattern| template::pattern_name(&pattern, &vars)) {
+            Some(name) => self.note_space.unique_name(&NoteSpace::join_name(folder, &name)),
+            None => {
+                let name = self.note_space.new_file_name(folder);
+                self.new_notes.insert(name.clone());
+                name
+            }
+        };
+
+        //create new file
+        vars.title = NoteSpace::base_name(&new_name);
+        let expanded = template::render(&template, &vars);
+        self.note_space.write_note(&new_name, &expanded.text)?;
+
+        //add link to parent
+        if let Some(parent_name) = parent {
+            self.add_link_to_parent(&parent_name, &new_name)?;
+        }
+        //flash data
+        self.note_space.flash_data();
+
+        //open new file
+        self.open(&new_name)?;
+        if let Some(line) = expanded.cursor_line {
+            self.execute_goto(format!("{}", line + 1));
+        }
+        Ok(())
+    }
+
+    /// append [[name]] to parent, the parent is created if not exist
+    fn add_link_to_parent(&mut self, parent: &str, name: &str) -> std::io::Result<()> {
+        if Some(parent.to_string()) == self.note_space.get_current_note() {
+            let _ = self.save();
+        }
+        let text = if self.note_space.is_file_exist(parent) {
+            self.note_space.read_note(parent)? + "\n\n"
+        } else {
+            String::new()
+        };
+        let text = text + "[[" + name + "]]";
+        self.note_space.write_note(parent, &text)
+    }
+
+    /// open the periodic note of date, create it from template if not exist
+    pub fn open_periodic(&mut self, period: Period, date: NaiveDate) -> std::io::Result<()> {
+        let name = self.config.periodic.note_name(period, date);
+        if !self.note_space.is_file_exist(&name) {
+            let cfg = self.config.periodic.cfg(period).clone();
+            let template = if cfg.template.is_empty() {
+                String::new()
+            } else {
+                self.note_space.read_note(&cfg.template).unwrap_or_default()
+            };
+            let mut vars = TemplateVars::new(Some(&cfg.parent));
+            vars.date = date.and_time(vars.date.time());
+            vars.title = PeriodicNotes::title(period, date);
+            let expanded = template::render(&template, &vars);
+            self.note_space.write_note(&name, &expanded.text)?;
+            if !cfg.parent.is_empty() {
+                self.add_link_to_parent(&cfg.parent, &name)?;
+            }
             self.note_space.flash_data();
+        }
+        self.open(&name)?;
+        Ok(())
+    }
+
+    pub fn open_periodic_today(&mut self, period: Period) -> std::io::Result<()> {
+        self.open_periodic(period, PeriodicNotes::today())
+    }
 
-            //open new file
-            self.open(&new_name)?;
+    /// open the previous (step < 0) or next periodic note of current note
+    /// the nearest existing one firstly, or create the adjacent one
+    pub fn open_periodic_sibling(&mut self, step: i32) -> std::io::Result<()> {
+        let Some(cur) = self.note_space.get_current_note() else {
+            return Ok(());
+        };
+        let notes = self.note_space.get_all_notes();
+        if let Some(sibling) = self.config.periodic.sibling_note(&notes, &cur, step) {
+            self.open(&sibling)?;
+        } else if let Some((period, date)) = self.config.periodic.find_period(&cur) {
+            if let Some(date) = PeriodicNotes::shift(period, date, step) {
+                self.open_periodic(period, date)?;
+            }
         }
         Ok(())
     }
 
-    pub fn rename_file(&mut self, org_name: &str, new_name: &str) -> std::io::Result<()> {
-        self.note_space.rename(org_name, new_name)?;
+    /// versions of current note
+    pub fn history_window_active(&mut self) {
+        if let Some(cur) = self.note_space.get_current_note() {
+            self.history_window.active(&cur, self.note_space.history());
+        }
+    }
 
+    /// restore the picked version into current note, it can be undone
+    pub fn history_window_show(&mut self, ui: &mut Ui) {
+        let ctx = self.note_space.get_current_cur().and_then(|cur| self.ectx_map.get(&cur));
+        if let Some(text) = self.history_window.show(ui, self.note_space.history(), ctx) {
+            if let Some(ctx) = self.cur_edit_ctx_mut() {
+                ctx.replace_all_text(&text);
+            }
+        }
+    }
+
+    pub fn export_window_show(&mut self, ui: &mut Ui) {
+        self.export_window.show(ui, &self.note_space);
+    }
+
+    pub fn import_window_show(&mut self, ui: &mut Ui) {
+        if let Some(root) = self.import_window.show(ui, &self.note_space) {
+            self.note_space.flash_data();
+            let _ = self.open(&root);
+        }
+    }
+
+    /// current note is a daily, weekly or monthly note
+    pub fn is_cur_periodic(&self) -> bool {
+        self.note_space.get_current_note()
+            .is_some_and(|cur| self.config.periodic.find_period(&cur).is_some())
+    }
+
+    /// replace [[org_name]] to [[new_name]] in all parents of org_name, aliases and anchors are kept
+    /// renamed: the parents which have been renamed too, (org, new)
+    fn replace_links_in_parents(&mut self, org_name: &str, new_name: &str, renamed: &HashMap<String, String>) -> std::io::Result<()> {
         for parent in self.note_space.get_parents(org_name) {
-            //change line content in parent file
+            let parent = renamed.get(&parent).cloned().unwrap_or(parent);
+            //change links in parent file, not the text in code
+            let text = self.note_space.read_note(&parent)?;
+            let (new_text, count) = MarkDownImpl::rewrite_links(&text, org_name, Some(new_name));
+            if count > 0 {
+                self.note_space.write_note(&parent, &new_text)?;
+                self.link_report.add(&parent, count);
+            }
+        }
+        Ok(())
+    }
+
+    /// parents which link to file and the line of link, the parents in skip or not existing are left out
+    fn parent_links(&self, file: &str, skip: &[String]) -> std::io::Result<Vec<ParentLink>> {
+        let mut links = vec![];
+        let targets = self.note_space.link_targets(file);
+        for parent in self.note_space.get_parents(file) {
+            if skip.contains(&parent) || !self.note_space.is_file_exist(&parent) {
+                continue;
+            }
+            //links in parent file, not the text in code, they are inserted back when restored
             let text = self.note_space.read_note(&parent)?;
-            let org_links = format!("[[{}]]", org_name);
-            let new_links = format!("[[{}]]", new_name);
-            let new_text = text.replace(&org_links, &new_links);
-            self.note_space.write_note(&parent, &new_text)?;
+            let (_, removed) = MarkDownImpl::remove_links(&text, &targets);
+            if let Some((offset, _)) = removed.first() {
+                let line = text[..*offset].matches('\n').count();
+                links.push(ParentLink { parent, line, removed });
+            }
+        }
+        Ok(links)
+    }
+
+    /// remove [[file]] and [[id:...]] of file from the parents
+    fn remove_links_in_parents(&mut self, file: &str, parents: &[ParentLink]) -> std::io::Result<()> {
+        let targets = self.note_space.link_targets(file);
+        for link in parents {
+            let text = self.note_space.read_note(&link.parent)?;
+            let (new_text, removed) = MarkDownImpl::remove_links(&text, &targets);
+            if !removed.is_empty() {
+                self.note_space.write_note(&link.parent, &new_text)?;
+                self.link_report.add(&link.parent, removed.len());
+            }
+        }
+        Ok(())
+    }
+
+    /// move the notes to trash firstly, then remove their links in parents
+    fn trash_notes(&mut self, notes: Vec<(String, Vec<ParentLink>)>) -> std::io::Result<()> {
+        for (note, parents) in notes {
+            self.note_space.delete_file(&note, parents.clone())?;
+            self.remove_links_in_parents(&note, &parents)?;
+            //unfixed from tool-bar
+            self.config.fixed_files.retain(|f| *f != note);
+        }
+        Ok(())
+    }
+
+    /// insert the removed links back to parents
+    fn restore_links_in_parents(&mut self, name: &str, links: &[ParentLink]) -> std::io::Result<()> {
+        for link in links {
+            if !self.note_space.is_file_exist(&link.parent) {
+                continue;
+            }
+            let text = self.note_space.read_note(&link.parent)?;
+            self.note_space.write_note(&link.parent, &link.restore(&text, name))?;
+        }
+        Ok(())
+    }
+
+    fn rename_in_config(&mut self, org_name: &str, new_name: &str) {
+        for file in self.config.fixed_files.iter_mut() {
+            if file == org_name {
+                *file = new_name.to_string();
+            }
+        }
+    }
+
+    pub fn rename_file(&mut self, org_name: &str, new_name: &str) -> std::io::Result<()> {
+        if Some(org_name.to_string()) == self.note_space.get_current_note() {
+            let _ = self.save();
         }
+        self.note_space.rename(org_name, new_name)?;
+        let _ = self.note_space.history().rename(org_name, new_name);
+        self.link_report.start(&format!("rename {} to {}", org_name, new_name));
+        self.replace_links_in_parents(org_name, new_name, &HashMap::new())?;
+        self.rename_in_config(org_name, new_name);
+        //the name is chosen by user, not by heading any more
+        self.new_notes.remove(org_name);
+
         //flash data
         self.note_space.flash_data();
 
@@ -265,30 +822,213 @@ impl Store {
     }
 
     pub fn delete_file(&mut self, file: &str) -> std::io::Result<()> {
-        self.note_space.delete_file(file)?;
-        let mut to_open= "help".to_string();
+        if Some(file.to_string()) == self.note_space.get_current_note() {
+            let _ = self.save();
+        }
+        self.link_report.start(&format!("delete {}", file));
+        let parents = self.parent_links(file, &[])?;
+        let to_open = parents.last().map(|p| p.parent.clone()).unwrap_or("help".to_string());
+        self.trash_notes(vec![(file.to_string(), parents)])?;
 
-        for parent in self.note_space.get_parents(file) {
-            //change line content in parent file
-            let text = self.note_space.read_note(&parent)?;
-            let org_links = format!("[[{}]]\n", file);
-            let new_text = text.replace(&org_links, "");
+        //flash data
+        self.note_space.flash_data();
+
+        //open parent file
+        self.open(&to_open)?;
+        Ok(())
+    }
+
+    /// move the note back from trash and insert its links to parents
+    pub fn restore_from_trash(&mut self, id: &str) -> std::io::Result<()> {
+        let _ = self.save();
+        let item = self.note_space.restore_from_trash(id)?;
+        self.restore_links_in_parents(&item.name, &item.parents)?;
+        self.note_space.flash_data();
+        self.open(&item.name)?;
+        Ok(())
+    }
+
+    /// move [[note]] from old parent to new parent, or reorder it between siblings
+    pub fn drop_link(&mut self, drop: LinkDrop) -> std::io::Result<()> {
+        //no link to itself or its descendant, it makes a cycle
+        if !drop.to.is_empty() && (drop.to == drop.note || self.note_space.is_descendant(&drop.to, &drop.note)) {
+            return Ok(());
+        }
+        let _ = self.save();
 
-            let org_links = format!("[[{}]]", file);
-            let new_text = new_text.replace(&org_links, "");
-            self.note_space.write_note(&parent, &new_text)?;
+        if drop.from != drop.to {
+            if !drop.to.is_empty() && !self.note_space.get_child_links(&drop.to).contains(&drop.note) {
+                match &drop.sibling {
+                    Some((sibling, after)) => {
+                        let text = self.note_space.read_note(&drop.to)?;
+                        let new_text = links::insert_link_line(&text, &drop.note, sibling, *after);
+                        self.note_space.write_note(&drop.to, &new_text)?;
+                    }
+                    None => self.add_link_to_parent(&drop.to, &drop.note)?,
+                }
+            }
+            if !drop.keep && !drop.from.is_empty() {
+                let mut text = self.note_space.read_note(&drop.from)?;
+                let mut changed = false;
+                for target in self.note_space.link_targets(&drop.note) {
+                    let (new_text, count) = MarkDownImpl::rewrite_links(&text, &target, None);
+                    text = new_text;
+                    changed |= count > 0;
+                }
+                if changed {
+                    self.note_space.write_note(&drop.from, &text)?;
+                }
+            }
+        } else if let Some((sibling, after)) = &drop.sibling {
+            let text = self.note_space.read_note(&drop.to)?;
+            if let Some(new_text) = links::move_link_line(&text, &drop.note, sibling, *after) {
+                self.note_space.write_note(&drop.to, &new_text)?;
+            }
+        }
+        self.note_space.flash_data();
 
-            to_open = parent;
+        //reload current note if it's a changed parent
+        if let Some(cur) = self.note_space.get_current_note() {
+            if cur == drop.from || cur == drop.to {
+                self.open(&cur)?;
+            }
         }
+        Ok(())
+    }
 
-        //unfixed from tool-bar
-        self.config.fixed_files.retain(|f| *f != file);
+    /// give the note an id in frontmatter if it has none, and copy "[[id:...]]" to clipboard
+    /// the link still works after the note is renamed or moved
+    pub fn copy_id_link(&mut self, name: &str) -> std::io::Result<()> {
+        let id = match self.note_space.note_id(name) {
+            Some(id) => id,
+            None => {
+                let _ = self.save();
+                let text = self.note_space.read_note(name)?;
+                let (front, body) = FrontMatter::split(&text);
+                let mut front = front.unwrap_or_else(FrontMatter::new);
+                let id = Uuid::now_v7().to_string();
+                front.set("id", &id);
+                self.note_space.write_note(name, &(front.to_text() + body))?;
+                self.note_space.flash_data();
+                if self.note_space.get_current_note().as_deref() == Some(name) {
+                    self.open(name)?;
+                }
+                id
+            }
+        };
+        let link = format!("[[{}{}]]", ID_LINK_PREFIX, id);
+        arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_text(link))
+            .map_err(|e| std::io::Error::other(e.to_string()))
+    }
 
-        //flash data
+    /// wrap the mention in [[link]], the mentioned note becomes a child of the note
+    pub fn link_mention(&mut self, mention: Mention) -> std::io::Result<()> {
+        //no link to its ancestor, it makes a cycle
+        if self.note_space.is_descendant(&mention.note, &mention.target) {
+            return Ok(());
+        }
+        self.save()?;
+
+        //saving may move the mention, find it again
+        let mut text = self.note_space.read_note(&mention.note)?;
+        let Some(range) = locate_mention(&text, &mention) else {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "the mention has changed"));
+        };
+        let link = if mention.text == mention.target {
+            format!("[[{}]]", mention.target)
+        } else {
+            format!("[[{}|{}]]", mention.target, mention.text)
+        };
+        text.replace_range(range, &link);
+        self.note_space.write_note(&mention.note, &text)?;
         self.note_space.flash_data();
 
-        //open parent file
-        self.open(&to_open)?;
+        if self.note_space.get_current_note().as_ref() == Some(&mention.note) {
+            self.open(&mention.note)?;
+        }
+        Ok(())
+    }
+
+    pub fn move_file(&mut self, file: &str, folder: &str) -> std::io::Result<()> {
+        let new_name = NoteSpace::join_name(folder, &NoteSpace::base_name(file));
+        if self.note_space.is_file_exist(&new_name) {
+            return Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, new_name));
+        }
+        self.rename_file(file, &new_name)
+    }
+
+    pub fn new_folder(&mut self, parent: Option<String>) -> std::io::Result<()> {
+        let name = self.note_space.new_folder_name(&parent.unwrap_or_default());
+        self.note_space.create_folder(&name)?;
+        self.note_space.flash_data();
+
+        //rename it at once
+        self.note_space.rename_window_active_folder(&name);
+        Ok(())
+    }
+
+    pub fn rename_folder(&mut self, org_folder: &str, new_folder: &str) -> std::io::Result<()> {
+        if self.note_space.is_folder_exist(new_folder) {
+            return Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, new_folder));
+        }
+        let _ = self.save();
+
+        //all notes in the folder get a new name
+        let prefix = format!("{}/", org_folder);
+        let mut renamed = HashMap::new();
+        for note in self.note_space.get_notes_in_folder(org_folder) {
+            let new_name = format!("{}/{}", new_folder, &note[prefix.len()..]);
+            renamed.insert(note, new_name);
+        }
+
+        self.note_space.rename_folder(org_folder, new_folder)?;
+        self.link_report.start(&format!("rename {} to {}", org_folder, new_folder));
+        for (org_name, new_name) in &renamed {
+            let _ = self.note_space.history().rename(org_name, new_name);
+            self.replace_links_in_parents(org_name, new_name, &renamed)?;
+            self.rename_in_config(org_name, new_name);
+        }
+        self.note_space.flash_data();
+
+        //reopen current note if it is moved
+        if let Some(cur) = self.note_space.get_current_note() {
+            if let Some(new_name) = renamed.get(&cur) {
+                self.open(&new_name.clone())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn move_folder(&mut self, folder: &str, to_folder: &str) -> std::io::Result<()> {
+        let new_folder = NoteSpace::join_name(to_folder, &NoteSpace::base_name(folder));
+        self.rename_folder(folder, &new_folder)
+    }
+
+    pub fn delete_folder(&mut self, folder: &str) -> std::io::Result<()> {
+        let notes = self.note_space.get_notes_in_folder(folder);
+        if let Some(cur) = self.note_space.get_current_note() {
+            if notes.contains(&cur) {
+                let _ = self.save();
+            }
+        }
+        self.link_report.start(&format!("delete {}", folder));
+        //only the links in parents out of the folder are removed, the notes in it go to trash together
+        let mut to_trash = vec![];
+        for note in &notes {
+            to_trash.push((note.clone(), self.parent_links(note, &notes)?));
+        }
+        let result = self.trash_notes(to_trash).and_then(|_| self.note_space.delete_folder(folder));
+        //links are changed even if it stops halfway
+        self.note_space.flash_data();
+        result?;
+
+        //current note is deleted, open help
+        if let Some(cur) = self.note_space.get_current_note() {
+            if notes.contains(&cur) {
+                self.open("help")?;
+            }
+        }
         Ok(())
     }
 
@@ -311,6 +1051,9 @@ impl Store {
             Command::OpenFile(file) => {
                 let _ = self.open(&file);
             }
+            Command::OpenHeading(note, heading) => {
+                let _ = self.open_heading(&note, &heading);
+            }
             Command::PathList(parent) => {
                 let links = self.note_space.get_child_links(&parent);
                 println!("{:?}", links);
@@ -318,12 +1061,58 @@ impl Store {
             Command::DeleteFile(file) => {
                 let _= self.delete_file(&file);
             }
+            Command::RestoreTrash(id) => {
+                let _= self.restore_from_trash(&id);
+            }
+            Command::PurgeTrash(id) => {
+                let _= self.note_space.purge_trash(id.as_deref());
+            }
             Command::NewFile(parent) => {
                 let _= self.new_note(parent);
             }
             Command::RenameFile(file) => {
                 self.note_space.rename_window_active(&file);
             }
+            Command::NewFileInFolder(folder) => {
+                let _= self.new_note_at(None, &folder);
+            }
+            Command::NewFolder(parent) => {
+                let _= self.new_folder(parent);
+            }
+            Command::RenameFolder(folder) => {
+                self.note_space.rename_window_active_folder(&folder);
+            }
+            Command::DeleteFolder(folder) => {
+                let _= self.delete_folder(&folder);
+            }
+            Command::MoveFile(file, folder) => {
+                let _= self.move_file(&file, &folder);
+            }
+            Command::FilterTag(tag) => {
+                if tag.is_some() && !self.config.show_index_window {
+                    self.config_update_show_index_window(true);
+                }
+                self.note_space.set_tag_filter(tag);
+            }
+            Command::DropLink(drop) => {
+                let _= self.drop_link(drop);
+            }
+            Command::LinkMention(mention) => {
+                let _= self.link_mention(mention);
+            }
+            Command::ExportSite(root) => {
+                let _= self.save();
+                self.export_window.active(&root, self.note_space.work_dir());
+            }
+            Command::CopyIdLink(name) => {
+                let _= self.copy_id_link(&name);
+            }
+            Command::ImportVault => {
+                self.import_window.active();
+            }
+            Command::MoveFolder(folder, to_folder) => {
+                let _= self.move_folder(&folder, &to_folder);
+            }
             Command::FixedFile(file) => {
                 self.config_fixed_file(file);
             }
@@ -337,6 +1126,12 @@ impl Store {
             }
             Command::FindReplace(mut param) => {
                 param.regex_build();
+                if let Some(FindCmd::FindInNotes) = param.cmd {
+                    self.vault_search.start(&self.note_space, param);
+                    self.tool_bar_info.is_show_bottom = true;
+                    self.tool_bar_info.show_note_search = true;
+                    return;
+                }
                 if let Some(edit_ctx) = self.cur_edit_ctx_mut() {
                     if let Some(find_cmd) = param.cmd.clone() {
                         match find_cmd {
@@ -357,7 +1152,9 @@ impl Store {
                             FindCmd::FindAll => {
                                 edit_ctx.find_all(&param);
                                 self.tool_bar_info.is_show_bottom = true;
+                                self.tool_bar_info.show_note_search = false;
                             },
+                            FindCmd::FindInNotes => {},
                         }   
                     }
                 }
@@ -444,6 +1241,31 @@ impl Store {
         self.config_save();
     }
 
+    pub fn config_switch_show_backlinks(&mut self) {
+        self.config.show_backlinks = !self.config.show_backlinks;
+        self.config_save();
+    }
+
+    pub fn config_switch_show_graph(&mut self) {
+        self.config.show_graph = !self.config.show_graph;
+        self.config_save();
+    }
+
+    pub fn config_switch_show_tags(&mut self) {
+        self.config.show_tags = !self.config.show_tags;
+        self.config_save();
+    }
+
+    pub fn config_switch_show_trash(&mut self) {
+        self.config.show_trash = !self.config.show_trash;
+        self.config_save();
+    }
+
+    pub fn config_switch_show_mentions(&mut self) {
+        self.config.show_mentions = !self.config.show_mentions;
+        self.config_save();
+    }
+
     pub fn config_restore(&mut self) {
         let config_file = self.note_space.config_file();
         if let Ok(json_str) = std::fs::read_to_string(&config_file) {
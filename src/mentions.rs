@@ -0,0 +1,303 @@
+use std::collections::HashSet;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::sync::Arc;
+use eframe::egui::{self, Button, Color32, ScrollArea, Ui, Widget, Window};
+use regex::RegexBuilder;
+use crate::medit::{Command, FrontMatter, IconName, MarkDownImpl, Mention};
+use crate::space::NoteSpace;
+use crate::ToolBar;
+
+#[derive(Clone, Debug)]
+pub struct MentionItem {
+    pub mention: Mention,
+    pub line_no: usize,     //line_no in editor
+    pub line_text: String,
+    pub is_cycle: bool,     //the note is a descendant of target, a link makes a cycle
+}
+
+/// ranges of terms in plain text which are not in [[links]], case is ignored
+/// code, frontmatter, images and markdown links are skipped
+pub fn find_mentions(text: &str, terms: &[String]) -> Vec<Range<usize>> {
+    let texts = MarkDownImpl::text_ranges(text);
+    let links: Vec<Range<usize>> = MarkDownImpl::wiki_links(text).into_iter().map(|link| link.range).collect();
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+
+    //longer terms first, "note book" wins over "note"
+    let mut terms: Vec<&String> = terms.iter().filter(|t| !t.trim().is_empty()).collect();
+    terms.sort_by_key(|t| std::cmp::Reverse(t.len()));
+
+    let mut ranges: Vec<Range<usize>> = vec![];
+    for term in terms {
+        let Ok(re) = RegexBuilder::new(&regex::escape(term)).case_insensitive(true).build() else {
+            continue;
+        };
+        let found = texts.iter()
+            .flat_map(|t| re.find_iter(&text[t.clone()]).map(move |m| t.start + m.start()..t.start + m.end()));
+        for range in found {
+            //whole word only for ascii words, other text has no spaces between words
+            let before = text[..range.start].chars().last();
+            let after = text[range.end..].chars().next();
+            let first = term.chars().next().unwrap_or(' ');
+            let last = term.chars().last().unwrap_or(' ');
+            if (is_word(first) && before.is_some_and(is_word)) || (is_word(last) && after.is_some_and(is_word)) {
+                continue;
+            }
+            let overlap = |r: &Range<usize>| r.start < range.end && range.start < r.end;
+            if links.iter().any(overlap) || ranges.iter().any(overlap) {
+                continue;
+            }
+            ranges.push(range);
+        }
+    }
+    ranges.sort_by_key(|r| r.start);
+    ranges
+}
+
+/// range of mention in text, the nearest one if the text before it has changed after found
+pub fn locate_mention(text: &str, mention: &Mention) -> Option<Range<usize>> {
+    find_mentions(text, std::slice::from_ref(&mention.text)).into_iter()
+        .filter(|r| text[r.clone()] == mention.text)
+        .min_by_key(|r| r.start.abs_diff(mention.offset))
+}
+
+/// places in other notes where the name or an alias of current note is not linked
+/// notes are read in a background thread
+pub struct MentionsPanel {
+    note: Option<String>,
+    data_version: usize,
+    items: Vec<MentionItem>,
+    receiver: Option<Receiver<Vec<MentionItem>>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl MentionsPanel {
+    pub fn new() -> Self {
+        Self {
+            note: None,
+            data_version: 0,
+            items: vec![],
+            receiver: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// base name and aliases in frontmatter
+    fn note_terms(note_space: &NoteSpace, name: &str) -> Vec<String> {
+        let mut terms = vec![NoteSpace::base_name(name)];
+        if let Ok(text) = note_space.read_note(name) {
+            if let Some(aliases) = FrontMatter::parse(&text).and_then(|front| front.get("aliases").map(|p| p.items())) {
+                terms.extend(aliases);
+            }
+        }
+        terms
+    }
+
+    /// mentions of target in text of note
+    pub fn find_items(note: &str, text: &str, target: &str, terms: &[String], is_cycle: bool) -> Vec<MentionItem> {
+        let mut items = vec![];
+        for range in find_mentions(text, terms) {
+            let Some(line_no) = NoteSpace::offset_to_line_no(text, range.start) else {
+                continue;
+            };
+            let line_start = text[..range.start].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = text[range.end..].find('\n').map_or(text.len(), |i| range.end + i);
+            let line_text = text[line_start..line_end].trim().to_string();
+            items.push(MentionItem {
+                mention: Mention {
+                    note: note.to_string(),
+                    offset: range.start,
+                    text: text[range].to_string(),
+                    target: target.to_string(),
+                },
+                line_no,
+                line_text,
+                is_cycle,
+            });
+        }
+        items
+    }
+
+    /// find in other notes of workspace, the items come by poll
+    fn start(&mut self, note_space: &NoteSpace, name: &str) {
+        let terms = Self::note_terms(note_space, name);
+        //descendants of target, a link from them makes a cycle
+        let mut descendants = HashSet::new();
+        let mut stack = vec![name.to_string()];
+        while let Some(n) = stack.pop() {
+            for child in note_space.get_child_links(&n) {
+                if descendants.insert(child.clone()) {
+                    stack.push(child);
+                }
+            }
+        }
+        let notes: Vec<(String, String, bool)> = note_space.get_all_notes().into_iter()
+            .filter(|note| note != name)
+            .map(|note| {
+                let path = note_space.name2path(&note);
+                let is_cycle = descendants.contains(&note);
+                (note, path, is_cycle)
+            })
+            .collect();
+
+        let (sender, receiver) = channel();
+        let cancel = self.cancel.clone();
+        let target = name.to_string();
+        std::thread::spawn(move || {
+            for (note, path, is_cycle) in notes {
+                if cancel.load(Ordering::Relaxed) {
+                    return;
+                }
+                let Ok(text) = std::fs::read_to_string(&path) else {
+                    continue;
+                };
+                let items = Self::find_items(&note, &text, &target, &terms, is_cycle);
+                if !items.is_empty() && sender.send(items).is_err() {
+                    return;
+                }
+            }
+        });
+        self.receiver = Some(receiver);
+    }
+
+    /// receive items from the thread
+    fn poll(&mut self) {
+        let Some(receiver) = &self.receiver else {
+            return;
+        };
+        loop {
+            match receiver.try_recv() {
+                Ok(mut items) => self.items.append(&mut items),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.receiver = None;
+                    break;
+                }
+            }
+        }
+    }
+
+    /// restart the finding when current note or the links have changed
+    pub fn flash(&mut self, note_space: &NoteSpace, force: bool) {
+        self.poll();
+        let note = note_space.get_current_note();
+        if !force && note == self.note && note_space.data_version() == self.data_version {
+            return;
+        }
+        //stop the last finding
+        self.cancel.store(true, Ordering::Relaxed);
+        self.cancel = Arc::new(AtomicBool::new(false));
+        self.receiver = None;
+        self.items = vec![];
+        if let Some(name) = &note {
+            self.start(note_space, name);
+        }
+        self.note = note;
+        self.data_version = note_space.data_version();
+    }
+
+    fn show_content(&mut self, ui: &mut Ui, is_show: &mut bool) -> Vec<Command> {
+        let mut cmds = vec![];
+        ui.horizontal(|ui| {
+            if ToolBar::tool_icon_button(ui, IconName::icon_close, false, false, "Close").clicked() {
+                *is_show = false;
+            }
+            ui.label(format!("Unlinked mentions: {} items", self.items.len()));
+            if self.receiver.is_some() {
+                ui.spinner();
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ToolBar::tool_icon_button(ui, IconName::icon_refresh, false, false, "Refresh").clicked() {
+                    self.data_version = 0;
+                }
+            });
+        });
+        ui.separator();
+
+        ScrollArea::both().auto_shrink(false).show(ui, |ui| {
+            let mut pre_note = String::new();
+            for item in &self.items {
+                //group by note
+                if item.mention.note != pre_note {
+                    pre_note = item.mention.note.clone();
+                    ui.add_space(4.0);
+                    if ui.add(Button::new(egui::RichText::new(&pre_note).strong()).fill(Color32::TRANSPARENT)).clicked() {
+                        cmds.push(Command::OpenFile(pre_note.clone()));
+                    }
+                }
+                ui.horizontal(|ui| {
+                    let r = ui.add_enabled(!item.is_cycle, Button::new("link it").small())
+                        .on_hover_text(format!("Replace \"{}\" with a link", item.mention.text))
+                        .on_disabled_hover_text("The note is a descendant of current note, a link makes a cycle");
+                    if r.clicked() {
+                        cmds.push(Command::LinkMention(item.mention.clone()));
+                    }
+                    let text = format!("{:>4} {}", item.line_no + 1, item.line_text);
+                    if Button::new(text).fill(Color32::TRANSPARENT).wrap().ui(ui).clicked() {
+                        cmds = vec![
+                            Command::OpenFile(item.mention.note.clone()),
+                            Command::ClickEditLine(format!("{} {}", item.line_no + 1, item.line_text)),
+                        ];
+                    }
+                });
+            }
+            if self.items.is_empty() && self.receiver.is_none() {
+                ui.weak("No unlinked mentions");
+            }
+        });
+        cmds
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, note_space: &NoteSpace, is_show: &mut bool) -> Vec<Command> {
+        let mut cmds = vec![];
+        if !*is_show {
+            return cmds;
+        }
+        self.flash(note_space, false);
+        if self.receiver.is_some() {
+            ctx.request_repaint();
+        }
+
+        let mut open = true;
+        Window::new("mentions")
+            .default_size([320.0, 360.0])
+            .title_bar(false)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                cmds = self.show_content(ui, is_show);
+            });
+        cmds
+    }
+}
+
+#[test]
+pub fn test_find_mentions() {
+    let text = "---\naliases: [Book]\n---\nA note, notes and [[note]], NOTE book.\n中文note本";
+    let terms = vec!["note".to_string(), "note book".to_string()];
+    let found: Vec<&str> = find_mentions(text, &terms).into_iter().map(|r| &text[r]).collect();
+    assert_eq!(found, vec!["note", "NOTE book", "note"]);
+
+    //code, images and markdown links are skipped
+    let text = "`note` ![](images/note.png) [a note](note.md) <https://x.com/note>\n\n```\nnote\n```\nthe note";
+    let ranges = find_mentions(text, &["note".to_string()]);
+    assert_eq!(ranges, vec![text.len() - 4..text.len()]);
+}
+
+#[test]
+pub fn test_find_items() {
+    //the same lines are different items, the mentions in frontmatter are skipped
+    let text = "---\ntitle: note\n---\nthe note\n\nthe note\n";
+    let items = MentionsPanel::find_items("p", text, "note", &["note".to_string()], false);
+    let lines: Vec<(usize, &str)> = items.iter().map(|i| (i.line_no, i.line_text.as_str())).collect();
+    assert_eq!(lines, vec![(0, "the note"), (1, "the note")]);
+}
+
+#[test]
+pub fn test_locate_mention() {
+    let mention = Mention { note: "p".to_string(), offset: 4, text: "note".to_string(), target: "note".to_string() };
+    assert_eq!(locate_mention("the note, a note", &mention), Some(4..8));
+    //saved with more text before it
+    assert_eq!(locate_mention("# P\n\nthe note, a note", &mention), Some(9..13));
+    assert_eq!(locate_mention("the [[note]]", &mention), None);
+}
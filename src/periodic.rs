@@ -0,0 +1,158 @@
+use serde::{Serialize, Deserialize};
+use chrono::{Datelike, Days, Local, Months, NaiveDate};
+use crate::space::NoteSpace;
+use crate::template::checked_format;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Period {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// settings of one kind of periodic note
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PeriodicCfg {
+    pub folder: String,     //folder of notes, "" is the top folder
+    pub format: String,     //chrono format of note name
+    pub template: String,   //note used as template, "" is empty
+    pub parent: String,     //new note is linked in this note, "" is no parent
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PeriodicNotes {
+    pub daily: PeriodicCfg,
+    pub weekly: PeriodicCfg,
+    pub monthly: PeriodicCfg,
+}
+
+impl Default for PeriodicNotes {
+    fn default() -> Self {
+        let cfg = |format: &str, parent: &str| PeriodicCfg {
+            folder: String::new(),
+            format: format.to_string(),
+            template: String::new(),
+            parent: parent.to_string(),
+        };
+        Self {
+            daily: cfg("%Y-%m-%d", "daily"),
+            weekly: cfg("%G-W%V", "weekly"),
+            monthly: cfg("%Y-%m", "monthly"),
+        }
+    }
+}
+
+impl PeriodicNotes {
+    pub fn cfg(&self, period: Period) -> &PeriodicCfg {
+        match period {
+            Period::Daily => &self.daily,
+            Period::Weekly => &self.weekly,
+            Period::Monthly => &self.monthly,
+        }
+    }
+
+    pub fn today() -> NaiveDate {
+        Local::now().date_naive()
+    }
+
+    /// the default format is used if the format of config is invalid
+    pub fn note_name(&self, period: Period, date: NaiveDate) -> String {
+        let name = date.format(&self.format(period)).to_string();
+        NoteSpace::join_name(&self.cfg(period).folder, &name)
+    }
+
+    /// format of period, the default one if it's invalid
+    fn format(&self, period: Period) -> String {
+        let format = &self.cfg(period).format;
+        if checked_format(NaiveDate::default().format(format)).is_some() {
+            format.clone()
+        } else {
+            Self::default().cfg(period).format.clone()
+        }
+    }
+
+    /// first day of the period of note, None if it isn't a periodic note
+    pub fn parse_note(&self, period: Period, name: &str) -> Option<NaiveDate> {
+        let cfg = self.cfg(period);
+        if NoteSpace::folder_of(name) != cfg.folder {
+            return None;
+        }
+        let base_name = NoteSpace::base_name(name);
+        let format = self.format(period);
+        let date = match period {
+            Period::Daily => NaiveDate::parse_from_str(&base_name, &format).ok()?,
+            //no day in name, parse with the first day
+            Period::Weekly => NaiveDate::parse_from_str(&format!("{}-1", base_name), &format!("{}-%u", format)).ok()?,
+            Period::Monthly => NaiveDate::parse_from_str(&format!("{}-01", base_name), &format!("{}-%d", format)).ok()?,
+        };
+        //only the name same as formatted, eg: "2025-2-4" isn't a daily note
+        if self.note_name(period, date) == name {
+            Some(date)
+        } else {
+            None
+        }
+    }
+
+    /// period and date of note
+    pub fn find_period(&self, name: &str) -> Option<(Period, NaiveDate)> {
+        [Period::Daily, Period::Weekly, Period::Monthly].into_iter()
+            .find_map(|period| self.parse_note(period, name).map(|date| (period, date)))
+    }
+
+    /// move date by steps of period
+    pub fn shift(period: Period, date: NaiveDate, step: i32) -> Option<NaiveDate> {
+        let n = step.unsigned_abs() as u64;
+        match (period, step >= 0) {
+            (Period::Daily, true) => date.checked_add_days(Days::new(n)),
+            (Period::Daily, false) => date.checked_sub_days(Days::new(n)),
+            (Period::Weekly, true) => date.checked_add_days(Days::new(n * 7)),
+            (Period::Weekly, false) => date.checked_sub_days(Days::new(n * 7)),
+            (Period::Monthly, true) => date.checked_add_months(Months::new(n as u32)),
+            (Period::Monthly, false) => date.checked_sub_months(Months::new(n as u32)),
+        }
+    }
+
+    /// the nearest existing note of same period before (step < 0) or after (step > 0) the note
+    pub fn sibling_note(&self, notes: &[String], name: &str, step: i32) -> Option<String> {
+        let (period, date) = self.find_period(name)?;
+        let dates = notes.iter().filter_map(|n| self.parse_note(period, n));
+        let sibling = if step < 0 {
+            dates.filter(|d| *d < date).max()
+        } else {
+            dates.filter(|d| *d > date).min()
+        };
+        sibling.map(|d| self.note_name(period, d))
+    }
+
+    /// title used in template of new note
+    pub fn title(period: Period, date: NaiveDate) -> String {
+        match period {
+            Period::Daily => date.format("%Y-%m-%d %A").to_string(),
+            Period::Weekly => format!("Week {} of {}", date.iso_week().week(), date.iso_week().year()),
+            Period::Monthly => date.format("%B %Y").to_string(),
+        }
+    }
+}
+
+#[test]
+pub fn test_periodic_notes() {
+    let notes = PeriodicNotes::default();
+    let date = NaiveDate::from_ymd_opt(2025, 2, 4).unwrap();
+    assert_eq!(notes.note_name(Period::Daily, date), "2025-02-04");
+    assert_eq!(notes.note_name(Period::Weekly, date), "2025-W06");
+    assert_eq!(notes.note_name(Period::Monthly, date), "2025-02");
+    assert_eq!(notes.find_period("2025-02-04"), Some((Period::Daily, date)));
+    assert_eq!(notes.find_period("2025-W06"), Some((Period::Weekly, NaiveDate::from_ymd_opt(2025, 2, 3).unwrap())));
+    assert_eq!(notes.find_period("2025-02"), Some((Period::Monthly, NaiveDate::from_ymd_opt(2025, 2, 1).unwrap())));
+    assert_eq!(notes.find_period("daily"), None);
+    let mut bad = PeriodicNotes::default();
+    bad.daily.format = "%Q".to_string();
+    assert_eq!(bad.note_name(Period::Daily, date), "2025-02-04");
+    assert_eq!(bad.find_period("2025-02-04"), Some((Period::Daily, date)));
+    assert_eq!(PeriodicNotes::shift(Period::Monthly, NaiveDate::from_ymd_opt(2025, 1, 31).unwrap(), 1), NaiveDate::from_ymd_opt(2025, 2, 28));
+
+    let all: Vec<String> = ["2025-02-01", "2025-02-04", "2025-02-06", "daily"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(notes.sibling_note(&all, "2025-02-04", -1), Some("2025-02-01".to_string()));
+    assert_eq!(notes.sibling_note(&all, "2025-02-04", 1), Some("2025-02-06".to_string()));
+    assert_eq!(notes.sibling_note(&all, "2025-02-06", 1), None);
+}
@@ -0,0 +1,64 @@
+use eframe::egui::{CollapsingHeader, Grid, TextEdit, Ui};
+use crate::medit::{Ctx, FrontMatter, IconName};
+use crate::medit::front::Property;
+use crate::ToolBar;
+
+/// frontmatter of note as a table of key/value at the top of editor
+pub fn show_properties(ui: &mut Ui, edit_ctx: &mut Ctx) {
+    if !edit_ctx.cfg().is_markdown {
+        return;
+    }
+    let mut changed = false;
+    let mut add = false;
+    let count = edit_ctx.front_matter().map_or(0, |front_matter| front_matter.props.len());
+
+    let title = format!("Properties ({})", count);
+    CollapsingHeader::new(title)
+        .id_source("note_properties")
+        .default_open(false)
+        .show(ui, |ui| {
+            if let Some(front_matter) = edit_ctx.front_matter_mut() {
+                let mut remove = None;
+                let value_width = (ui.available_width() - 180.0).max(120.0);
+                Grid::new("note_properties_grid").num_columns(3).striped(true).show(ui, |ui| {
+                    for (i, prop) in front_matter.props.iter_mut().enumerate() {
+                        let editable = !prop.is_read_only();
+                        let r1 = TextEdit::singleline(&mut prop.key).hint_text("key").desired_width(120.0).interactive(editable).show(ui).response;
+                        let r2 = TextEdit::singleline(&mut prop.value).hint_text("value").desired_width(value_width).interactive(editable).show(ui).response;
+                        if !editable {
+                            r2.on_hover_text("Block text or nested keys can't be edited here");
+                        } else if r1.changed() || r2.changed() {
+                            prop.edited = true;
+                            changed = true;
+                        }
+                        if ToolBar::tool_icon_button(ui, IconName::icon_delete, false, false, "Delete property").clicked() {
+                            remove = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+                if let Some(i) = remove {
+                    front_matter.remove(i);
+                    changed = true;
+                }
+            }
+            if ui.small_button("+ add property").clicked() {
+                add = true;
+            }
+        });
+
+    //the first property of a note creates the frontmatter
+    if add {
+        match edit_ctx.front_matter_mut() {
+            Some(front_matter) => front_matter.props.push(Property::new("", "")),
+            None => {
+                let mut front_matter = FrontMatter::new();
+                front_matter.props.push(Property::new("", ""));
+                edit_ctx.set_front_matter(Some(front_matter));
+            }
+        }
+    }
+    if changed {
+        edit_ctx.front_matter_changed();
+    }
+}
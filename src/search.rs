@@ -0,0 +1,229 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::path::Path;
+use crate::medit::{Command, Ctx, FindReplaceCtx};
+use crate::space::NoteSpace;
+
+#[derive(Clone, Debug)]
+pub struct NoteHit {
+    pub note: String,
+    pub line_no: usize,     //line_no in editor
+    pub line_text: String,
+}
+
+enum SearchMsg {
+    Hits(Vec<NoteHit>),
+    Progress(usize),
+    Done,
+}
+
+/// find in all notes of workspace, run in a background thread
+pub struct VaultSearch {
+    receiver: Option<Receiver<SearchMsg>>,
+    cancel: Arc<AtomicBool>,
+    param: FindReplaceCtx,
+    hits: Vec<NoteHit>,
+    line_hits: Vec<Option<usize>>,  //line of result view -> index of hits
+    note_count: usize,
+    searched: usize,
+    pub edit_ctx: Ctx,
+}
+
+impl VaultSearch {
+    pub fn new() -> Self {
+        Self {
+            receiver: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+            param: FindReplaceCtx::new(),
+            hits: vec![],
+            line_hits: vec![],
+            note_count: 0,
+            searched: 0,
+            edit_ctx: Self::result_ctx(""),
+        }
+    }
+
+    fn result_ctx(text: &str) -> Ctx {
+        let mut edit_ctx = Ctx::new(text, false, None);
+        edit_ctx.cfg_mut().need_line_click_cmd = true;
+        edit_ctx.cfg_mut().hightlight_seleted_word = false;
+        edit_ctx
+    }
+
+    /// find in lines of one note
+    pub fn find_in_lines(note: &str, lines: &[String], param: &FindReplaceCtx) -> Vec<NoteHit> {
+        lines.iter().enumerate()
+            .filter(|(_, line)| !Ctx::find_func(line, param).is_empty())
+            .map(|(line_no, line)| NoteHit {
+                note: note.to_string(),
+                line_no,
+                line_text: line.clone(),
+            })
+            .collect()
+    }
+
+    pub fn start(&mut self, note_space: &NoteSpace, param: FindReplaceCtx) {
+        //stop the last search
+        self.cancel.store(true, Ordering::Relaxed);
+        self.cancel = Arc::new(AtomicBool::new(false));
+
+        self.hits = vec![];
+        self.searched = 0;
+        self.param = param.clone();
+        self.clear_view();
+        if param.find.is_empty() {
+            self.receiver = None;
+            return;
+        }
+
+        //skip the notes which can not match by index, unless changed after indexed
+        let candidates = note_space.index().candidates(&param);
+        let notes: Vec<(String, String)> = note_space.get_all_notes().into_iter()
+            .map(|name| {
+                let path = note_space.name2path(&name);
+                (name, path)
+            })
+            .filter(|(name, path)| match &candidates {
+                Some(candidates) => candidates.contains(name) || !note_space.index().is_fresh(name, Path::new(path)),
+                None => true,
+            })
+            .collect();
+        self.note_count = notes.len();
+
+        let (sender, receiver) = channel();
+        let cancel = self.cancel.clone();
+        std::thread::spawn(move || {
+            for (i, (name, path)) in notes.iter().enumerate() {
+                if cancel.load(Ordering::Relaxed) {
+                    return;
+                }
+                if let Ok(text) = std::fs::read_to_string(path) {
+                    let lines = NoteSpace::text_to_lines(&text);
+                    let hits = Self::find_in_lines(name, &lines, &param);
+                    if !hits.is_empty() && sender.send(SearchMsg::Hits(hits)).is_err() {
+                        return;
+                    }
+                }
+                let _ = sender.send(SearchMsg::Progress(i + 1));
+            }
+            let _ = sender.send(SearchMsg::Done);
+        });
+        self.receiver = Some(receiver);
+    }
+
+    /// stop the search thread, the results are kept
+    pub fn stop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+        self.receiver = None;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    /// receive results from search thread, return true if changed
+    pub fn poll(&mut self) -> bool {
+        let mut batches = vec![];
+        let mut done = false;
+        if let Some(receiver) = &self.receiver {
+            while let Ok(msg) = receiver.try_recv() {
+                match msg {
+                    SearchMsg::Hits(hits) => {
+                        batches.push(hits);
+                    }
+                    SearchMsg::Progress(searched) => {
+                        self.searched = searched;
+                    }
+                    SearchMsg::Done => {
+                        done = true;
+                    }
+                }
+            }
+        }
+        if done {
+            self.receiver = None;
+        }
+        let changed = !batches.is_empty();
+        for hits in batches {
+            self.append_view(hits);
+        }
+        changed || done
+    }
+
+    fn clear_view(&mut self) {
+        self.line_hits = vec![];
+        self.edit_ctx = Self::result_ctx("");
+    }
+
+    /// hits of one note are added to the end of result view, grouped by note
+    fn append_view(&mut self, mut hits: Vec<NoteHit>) {
+        let Some(first) = hits.first() else {
+            return;
+        };
+        let is_first = self.line_hits.is_empty();
+        let max_line_no = hits.iter().map(|h| h.line_no + 1).max().unwrap_or(1).to_string().len();
+        let mut lines = vec![format!("{}: {} items", first.note, hits.len())];
+        self.line_hits.push(None);
+        for (i, hit) in hits.iter().enumerate() {
+            lines.push(format!("    {:>max_line_no$} {}", hit.line_no + 1, hit.line_text));
+            self.line_hits.push(Some(self.hits.len() + i));
+        }
+        self.hits.append(&mut hits);
+
+        //the empty view has one empty line
+        let text = lines.join("\n");
+        let from_line = if is_first {
+            self.edit_ctx = Self::result_ctx(&text);
+            0
+        } else {
+            let from_line = self.edit_ctx.line_num();
+            self.edit_ctx.append_lines(&text);
+            from_line
+        };
+        self.edit_ctx.append_same_cache_with_param(&self.param, from_line);
+    }
+
+    pub fn title(&self) -> String {
+        //a header line for each note
+        let notes = self.line_hits.len() - self.hits.len();
+        let mut title = format!("Find in notes: match {} items in {} notes", self.hits.len(), notes);
+        if self.is_running() {
+            title += &format!(", searching {}/{}", self.searched, self.note_count);
+        }
+        title
+    }
+
+    /// clicked line of result view to open note command
+    pub fn take_cmds(&mut self) -> Vec<Command> {
+        let mut cmds = vec![];
+        while let Some(cmd) = self.edit_ctx.pop_cmd() {
+            if let Command::ClickEditLine(_) = cmd {
+                let line = self.edit_ctx.cursor2().line_no;
+                if let Some(Some(i)) = self.line_hits.get(line) {
+                    let hit = &self.hits[*i];
+                    cmds.push(Command::OpenFile(hit.note.clone()));
+                    cmds.push(Command::ClickEditLine(format!("{} {}", hit.line_no + 1, hit.line_text)));
+                } else if let Some(hit) = self.line_hits.iter().skip(line).flatten().next().map(|i| &self.hits[*i]) {
+                    //note header line
+                    cmds.push(Command::OpenFile(hit.note.clone()));
+                }
+            }
+        }
+        cmds
+    }
+}
+
+#[test]
+pub fn test_find_in_lines() {
+    let lines: Vec<String> = vec!["hello world".to_string(), "Hello abc".to_string(), "helloworld".to_string()];
+    let mut param = FindReplaceCtx::new();
+    param.find = "hello".to_string();
+    assert_eq!(VaultSearch::find_in_lines("a", &lines, &param).len(), 3);
+    param.is_case = true;
+    assert_eq!(VaultSearch::find_in_lines("a", &lines, &param).len(), 2);
+    param.is_hole_word = true;
+    let hits = VaultSearch::find_in_lines("a", &lines, &param);
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].line_no, 0);
+}
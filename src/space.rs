@@ -1,17 +1,25 @@
 use core::f32;
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::{fs, vec};
-use std::path::PathBuf;
-use crate::medit::ctx::EditCfg;
-use crate::medit::{IconName, MarkDownImpl, Command};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::medit::ctx::{EditCfg, NoteIds};
+use crate::medit::{FrontMatter, IconName, MarkDownImpl, Command, LinkDrop, ID_LINK_PREFIX};
 use crate::ToolBar;
 use crate::mem::Config;
-use eframe::egui::{collapsing_header, Button, Color32, Frame, Rect, Stroke, Ui, Widget, Window, Vec2, Response, Order};
+use crate::index::NoteIndex;
+use crate::trash::{ParentLink, Trash, TrashItem};
+use crate::history::NoteHistory;
+use crate::git::NoteRepo;
+use eframe::egui::{collapsing_header, Button, Color32, ComboBox, EventFilter, Frame, Id, Key, Rect, RichText, Sense, Stroke, TextEdit, Ui, Widget, Window, Vec2, Response, Order};
+use serde::{Serialize, Deserialize};
 
 #[derive(Debug)]
 pub struct  RenameWin {
     is_show: bool,
     need_focus: bool,
+    is_folder: bool,
     org_name: String,
     new_name: String,
 }
@@ -21,14 +29,16 @@ impl RenameWin {
         Self {
             is_show: false,
             need_focus: false,
+            is_folder: false,
             org_name: String::new(),
             new_name: String::new(),
         }
     }
 
-    fn active(&mut self, name: &str) {
+    fn active(&mut self, name: &str, is_folder: bool) {
         self.is_show = true;
         self.need_focus = true;
+        self.is_folder = is_folder;
         self.org_name = name.to_string();
         self.new_name = name.to_string();
     }
@@ -51,7 +61,7 @@ impl RenameWin {
             rect.min = pointer_pos;
         }
         let mut need_rename = false;
-        let title = format!("rename");
+        let title = if self.is_folder {"rename folder"} else {"rename"};
         let egui_ctx = ui.ctx();
         Window::new(title)
             .default_rect(rect)
@@ -80,12 +90,57 @@ impl RenameWin {
     }
 }
 
+/// order of notes in index tree
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum TreeSort {
+    #[default]
+    Manual,     //children in the order of links, roots by name
+    Name,
+    Modified,   //newest first
+    Created,    //newest first
+}
+
+impl TreeSort {
+    pub const ALL: [TreeSort; 4] = [TreeSort::Manual, TreeSort::Name, TreeSort::Modified, TreeSort::Created];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TreeSort::Manual => "Manual",
+            TreeSort::Name => "Name",
+            TreeSort::Modified => "Modified",
+            TreeSort::Created => "Created",
+        }
+    }
+}
+
+/// (ancestors, name) of a line in index tree, folders are ([], "folder/")
+/// a note is shown under each of its parents, the path from root keeps the lines apart
+type TreeKey = (Vec<String>, String);
+
+/// a line of index tree, collected when the tree is shown, for keyboard navigation
+#[derive(Clone, Debug)]
+struct TreeRow {
+    key: TreeKey,
+    parent: Option<usize>,  //index of the parent line
+    can_open: bool,
+    is_open: bool,
+}
+
 pub struct IndexWind {
     pub is_show: bool,
     pub must_at_top: bool,
     pub need_open: Option<String>,
     pub delete_confirm: Option<String>,
     pub is_window :bool,
+    pub tag_filter: Option<String>,     //only show notes with the tag
+    filter: String,                     //text of filter box
+    filter_key: (String, usize),        //(filter, data_version) of visible
+    visible: Option<HashSet<String>>,   //notes shown when filtering, None if no filter
+    rows: Vec<TreeRow>,
+    row_stack: Vec<usize>,              //lines of the open nodes being shown
+    selected: Option<TreeKey>,
+    scroll_to_selected: bool,
+    toggle: Option<(TreeKey, bool)>,    //open or close the node by keys
 }
 
 impl IndexWind {
@@ -96,6 +151,15 @@ impl IndexWind {
             need_open: None,
             delete_confirm: None,
             is_window: false,
+            tag_filter: None,
+            filter: String::new(),
+            filter_key: (String::new(), 0),
+            visible: None,
+            rows: vec![],
+            row_stack: vec![],
+            selected: None,
+            scroll_to_selected: false,
+            toggle: None,
         }
     }
 }
@@ -144,22 +208,125 @@ impl NoteSpace {
         ui.painter().circle_filled(response.rect.center(), 2.0, stroke.color);
     }
     
-    /// return if need open one file
-    fn show_sub_index(&mut self, config: &mut Config, ui: &mut Ui, name: &str, deep: usize) -> Option<Command> {
+    //drop the dragged note on the line of name, the top or bottom edge of line is between siblings
+    fn link_drop(ui: &Ui, rect: Rect, name: &str, parent: &str, drag: &DragNote) -> Option<LinkDrop> {
+        if drag.name == name {
+            return None;
+        }
+        let pos = ui.ctx().pointer_interact_pos()?;
+        let edge = rect.height() / 4.0;
+        let sibling = if name == "." || parent.is_empty() {
+            None
+        } else if pos.y < rect.top() + edge {
+            Some((name.to_string(), false))
+        } else if pos.y > rect.bottom() - edge {
+            Some((name.to_string(), true))
+        } else {
+            None
+        };
+        let to = match (&sibling, name) {
+            (Some(_), _) => parent.to_string(),
+            (None, ".") => String::new(),   //to root, only removed from the parent
+            (None, _) => name.to_string(),
+        };
+        let keep = ui.input(|i| i.modifiers.alt);
+        if (sibling.is_none() && to == drag.parent) || (to.is_empty() && keep) {
+            return None;
+        }
+        Some(LinkDrop { note: drag.name.clone(), from: drag.parent.clone(), to, sibling, keep })
+    }
+
+    /// a link back to an ancestor, it's a leaf so the cycle isn't expanded again
+    fn show_back_ref(&mut self, ui: &mut Ui, name: &str, ancestors: &[String]) -> Option<Command> {
         let mut cmd = None;
-        if deep > 10 {
-            return cmd;
+        let key = (ancestors.to_vec(), name.to_string());
+        let is_selected = self.push_tree_row(key.clone(), false, false);
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 2.0;
+            ui.add_space(ui.spacing().icon_width);
+            let text = RichText::new(format!("\u{21bb} {}", Self::base_name(name))).color(ui.visuals().weak_text_color());
+            let r = Button::new(text).fill(Color32::TRANSPARENT).selected(is_selected).ui(ui)
+                .on_hover_text(format!("back-reference to ancestor {}", name));
+            self.tree_row_response(ui, &r, key, is_selected);
+            if r.clicked() {
+                self.index_window.need_open = Some(name.to_string());
+                cmd = Some(Command::OpenFile(name.to_string()));
+            }
+        });
+        cmd
+    }
+
+    /// add a line to the rows of tree, return true if it's selected
+    fn push_tree_row(&mut self, key: TreeKey, can_open: bool, is_open: bool) -> bool {
+        let is_selected = self.index_window.selected.as_ref() == Some(&key);
+        let parent = self.index_window.row_stack.last().copied();
+        self.index_window.rows.push(TreeRow { key, parent, can_open, is_open });
+        is_selected
+    }
+
+    /// a clicked line is selected and the tree gets keyboard focus
+    fn tree_row_response(&mut self, ui: &Ui, r: &Response, key: TreeKey, is_selected: bool) {
+        if r.clicked() {
+            self.index_window.selected = Some(key);
+            ui.memory_mut(|mem| mem.request_focus(Self::tree_id()));
+        } else if is_selected && self.index_window.scroll_to_selected {
+            r.scroll_to_me(None);
         }
-        let childs = self.get_child_links(name);
-        let id = ui.make_persistent_id(name);
+    }
+
+    fn tree_id() -> Id {
+        Id::new("index_tree")
+    }
+
+    /// the node is opened or closed by keys
+    fn take_tree_toggle(&mut self, key: &TreeKey) -> Option<bool> {
+        match &self.index_window.toggle {
+            Some((k, open)) if k == key => {
+                let open = *open;
+                self.index_window.toggle = None;
+                Some(open)
+            }
+            _ => None,
+        }
+    }
+
+    fn is_tree_visible(&self, name: &str) -> bool {
+        self.index_window.visible.as_ref().map_or(true, |v| v.contains(name))
+    }
+
+    /// return if need open one file
+    /// parent: the parent of note in tree, "" for root notes
+    /// ancestors: notes from the root to parent, children are only built when the node is open
+    fn show_sub_index(&mut self, config: &mut Config, ui: &mut Ui, name: &str, parent: &str, ancestors: &[String]) -> Option<Command> {
+        if name != "." && !self.is_tree_visible(name) {
+            return None;
+        }
+        if ancestors.iter().any(|a| a == name) {
+            return self.show_back_ref(ui, name, ancestors);
+        }
+        let mut cmd = None;
+        let mut childs = self.get_child_links(name);
+        self.sort_notes(&mut childs, config.tree_sort);
+        //all nodes are open when filtering, their own state isn't changed
+        let filtering = self.index_window.visible.is_some();
+        let id = if filtering {ui.make_persistent_id(("filter", name))} else {ui.make_persistent_id(name)};
         let is_open = config.tree_open_state_is_open(name);
+        let base_name = Self::base_name(name);
         let show_name = if !is_open && childs.len() > 0 {
-            &format!("{}...{}", name, childs.len())
+            &format!("{}...{}", base_name, childs.len())
         } else {
-            &format!("{}", name)
+            &base_name
         };
-        let mut state = collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, is_open);
-        config.tree_open_state_update(name, state.is_open());
+        let mut state = collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, is_open || filtering);
+        let key = (ancestors.to_vec(), name.to_string());
+        if let Some(open) = self.take_tree_toggle(&key) {
+            state.set_open(open);
+        }
+        if !filtering {
+            config.tree_open_state_update(name, state.is_open());
+        }
+        let is_selected = name != "." && self.push_tree_row(key.clone(), !childs.is_empty(), state.is_open());
+        let row = self.index_window.rows.len().checked_sub(1).filter(|_| name != ".");
         let header_res = ui.horizontal(|ui|{
             ui.spacing_mut().item_spacing.x = 2.0;
 
@@ -172,12 +339,50 @@ impl NoteSpace {
             let r = if name == "." {
                 ui.label("Note")
             } else {
-                Button::new(show_name).fill(Color32::TRANSPARENT).ui(ui)
+                let r = Button::new(show_name).fill(Color32::TRANSPARENT).selected(is_selected).sense(Sense::click_and_drag()).ui(ui);
+                //drag to another parent, alt-drag to add a parent
+                r.dnd_set_drag_payload(DragNote { name: name.to_string(), parent: parent.to_string() });
+                self.tree_row_response(ui, &r, key.clone(), is_selected);
+                r.on_hover_text(self.note_hover_text(name))
             };
             if r.clicked() {
                 self.index_window.need_open = Some(name.to_string());
                 cmd = Some(Command::OpenFile(name.to_string()));
             }
+            r.context_menu(|ui| {
+                if name == "." {
+                    if ui.button("New folder").clicked() {
+                        ui.close_menu();
+                        cmd = Some(Command::NewFolder(None));
+                    }
+                    if ui.button("Export site").clicked() {
+                        ui.close_menu();
+                        cmd = Some(Command::ExportSite(name.to_string()));
+                    }
+                    if ui.button("Import vault").clicked() {
+                        ui.close_menu();
+                        cmd = Some(Command::ImportVault);
+                    }
+                } else {
+                    if ui.button("Export site").clicked() {
+                        ui.close_menu();
+                        cmd = Some(Command::ExportSite(name.to_string()));
+                    }
+                    let folder = Self::folder_of(name);
+                    ui.menu_button("Move to", |ui| {
+                        for target in self.get_folders() {
+                            if target != folder && Self::folder_button(ui, &target).clicked() {
+                                ui.close_menu();
+                                cmd = Some(Command::MoveFile(name.to_string(), target));
+                            }
+                        }
+                    });
+                    if ui.button("Copy ID link").on_hover_text("Link by id, it works after rename").clicked() {
+                        ui.close_menu();
+                        cmd = Some(Command::CopyIdLink(name.to_string()));
+                    }
+                }
+            });
 
             let is_fiex_in_toolbar = config.fixed_files.contains(&name.to_string());
 
@@ -237,19 +442,286 @@ impl NoteSpace {
             }
         });
 
+        //drop a dragged note on this line
+        let line_rect = header_res.response.rect;
+        if let Some(drag) = header_res.response.dnd_hover_payload::<DragNote>() {
+            if let Some(drop) = Self::link_drop(ui, line_rect, name, parent, &drag) {
+                let stroke = Stroke::new(2.0, ui.visuals().selection.bg_fill);
+                match &drop.sibling {
+                    Some((_, false)) => ui.painter().hline(line_rect.x_range(), line_rect.top(), stroke),
+                    Some((_, true)) => ui.painter().hline(line_rect.x_range(), line_rect.bottom(), stroke),
+                    None => ui.painter().rect_stroke(line_rect, 2.0, stroke),
+                };
+                if header_res.response.dnd_release_payload::<DragNote>().is_some() {
+                    cmd = Some(Command::DropLink(drop));
+                }
+            }
+        }
+
         state.show_body_indented(&header_res.response, ui, |ui| {
+            if let Some(row) = row {
+                self.index_window.row_stack.push(row);
+            }
+            //root: only the notes with the tag
+            let childs = if name == "." && self.index_window.tag_filter.is_some() {
+                let tag = self.index_window.tag_filter.clone().unwrap_or_default();
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(format!("#{}", tag)).color(ui.visuals().hyperlink_color));
+                    if ToolBar::tool_icon_button(ui, IconName::icon_close, false, false, "Clear tag filter").clicked() {
+                        cmd = Some(Command::FilterTag(None));
+                    }
+                });
+                let mut notes = self.get_tag_notes(&tag);
+                self.sort_notes(&mut notes, config.tree_sort);
+                notes
+            } else if name == "." {
+                //root: folders first, then the notes in top folder
+                for folder in self.get_sub_folders("") {
+                    let sub_cmd = self.show_folder_index(config, ui, &folder);
+                    if sub_cmd.is_some() {
+                        cmd = sub_cmd;
+                    }
+                }
+                let mut roots = self.get_folder_root_files("");
+                self.sort_notes(&mut roots, config.tree_sort);
+                roots
+            } else {
+                childs
+            };
+            //children of root have no parent link
+            let child_parent = if name == "." {""} else {name};
+            let mut child_ancestors = ancestors.to_vec();
+            if name != "." {
+                child_ancestors.push(name.to_string());
+            }
             for c in childs {
-                let sub_cmd = self.show_sub_index(config, ui, &c, deep+1);
+                let sub_cmd = self.show_sub_index(config, ui, &c, child_parent, &child_ancestors);
+                if sub_cmd.is_some() {
+                    cmd = sub_cmd;
+                }
+            }
+            if row.is_some() {
+                self.index_window.row_stack.pop();
+            }
+        });
+        cmd
+    }
+
+    //full name and properties of the note
+    fn note_hover_text(&self, name: &str) -> String {
+        let mut text = name.to_string();
+        for (key, value) in self.get_properties(name) {
+            text += &format!("\n{}: {}", key, value);
+        }
+        text
+    }
+
+    fn folder_button(ui: &mut Ui, folder: &str) -> Response {
+        let text = if folder.is_empty() { "/" } else { folder };
+        ui.button(text)
+    }
+
+    /// folder node in index tree, shows sub folders and the link roots in this folder
+    fn show_folder_index(&mut self, config: &mut Config, ui: &mut Ui, folder: &str) -> Option<Command> {
+        let mut cmd = None;
+        let key = format!("{}/", folder);   //tree key of folder, note name never ends with '/'
+        //when filtering, only the folders with shown roots
+        let filtering = self.index_window.visible.is_some();
+        if filtering && !self.get_root_files().iter().any(|r| r.starts_with(&key) && self.is_tree_visible(r)) {
+            return None;
+        }
+        let id = if filtering {ui.make_persistent_id(("filter", &key))} else {ui.make_persistent_id(&key)};
+        let is_open = config.tree_open_state_is_open(&key);
+        let mut state = collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, is_open || filtering);
+        let row_key = (vec![], key.clone());
+        if let Some(open) = self.take_tree_toggle(&row_key) {
+            state.set_open(open);
+        }
+        if !filtering {
+            config.tree_open_state_update(&key, state.is_open());
+        }
+        let is_selected = self.push_tree_row(row_key.clone(), true, state.is_open());
+        let row = self.index_window.rows.len() - 1;
+        let header_res = ui.horizontal(|ui|{
+            ui.spacing_mut().item_spacing.x = 2.0;
+            state.show_toggle_button(ui, collapsing_header::paint_default_icon);
+
+            let show_name = format!("{}/", Self::base_name(folder));
+            let r = Button::new(show_name).fill(Color32::TRANSPARENT).selected(is_selected).ui(ui).on_hover_text(folder);
+            self.tree_row_response(ui, &r, row_key.clone(), is_selected);
+            if r.clicked() {
+                state.toggle(ui);
+            }
+            r.context_menu(|ui| {
+                if ui.button("New note").clicked() {
+                    ui.close_menu();
+                    cmd = Some(Command::NewFileInFolder(folder.to_string()));
+                }
+                if ui.button("New folder").clicked() {
+                    ui.close_menu();
+                    cmd = Some(Command::NewFolder(Some(folder.to_string())));
+                }
+                if ui.button("Rename folder").clicked() {
+                    ui.close_menu();
+                    cmd = Some(Command::RenameFolder(folder.to_string()));
+                }
+                ui.menu_button("Move to", |ui| {
+                    let parent = Self::folder_of(folder);
+                    for target in self.get_folders() {
+                        //can't move into itself or its sub folders
+                        if target == parent || target == folder || target.starts_with(&key) {
+                            continue;
+                        }
+                        if Self::folder_button(ui, &target).clicked() {
+                            ui.close_menu();
+                            cmd = Some(Command::MoveFolder(folder.to_string(), target));
+                        }
+                    }
+                });
+                if ui.button("Delete folder").clicked() {
+                    ui.close_menu();
+                    self.index_window.delete_confirm = Some(key.clone()); //show comfirm window
+                }
+            });
+        });
+
+        state.show_body_indented(&header_res.response, ui, |ui| {
+            self.index_window.row_stack.push(row);
+            for sub_folder in self.get_sub_folders(folder) {
+                let sub_cmd = self.show_folder_index(config, ui, &sub_folder);
+                if sub_cmd.is_some() {
+                    cmd = sub_cmd;
+                }
+            }
+            let mut roots = self.get_folder_root_files(folder);
+            self.sort_notes(&mut roots, config.tree_sort);
+            for c in roots {
+                let sub_cmd = self.show_sub_index(config, ui, &c, "", &[]);
                 if sub_cmd.is_some() {
                     cmd = sub_cmd;
                 }
             }
+            self.index_window.row_stack.pop();
         });
         cmd
     }
 
+    /// filter box and sort mode above the tree
+    fn show_tree_options(&mut self, config: &mut Config, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            let r = TextEdit::singleline(&mut self.index_window.filter)
+                .hint_text("Filter")
+                .desired_width((ui.available_width() - 100.0).max(60.0))
+                .ui(ui);
+            //down arrow goes from filter box to the tree
+            if r.has_focus() && ui.input(|i| i.key_pressed(Key::ArrowDown)) {
+                ui.memory_mut(|mem| mem.request_focus(Self::tree_id()));
+                self.index_window.selected = self.index_window.rows.first().map(|row| row.key.clone());
+            }
+            let mut sort = config.tree_sort;
+            ComboBox::from_id_source("tree_sort")
+                .selected_text(sort.label())
+                .width(80.0)
+                .show_ui(ui, |ui| {
+                    for s in TreeSort::ALL {
+                        ui.selectable_value(&mut sort, s, s.label());
+                    }
+                });
+            if sort != config.tree_sort {
+                config.tree_sort = sort;
+                config.tree_open_state_changed = true;  //config is saved
+            }
+        });
+        self.update_filter();
+    }
+
+    /// arrows move the selected line, Enter opens, F2 renames and Del deletes it
+    fn tree_keys(&mut self, ui: &Ui) -> Option<Command> {
+        let rows = &self.index_window.rows;
+        if rows.is_empty() {
+            return None;
+        }
+        let (up, down, left, right, enter, f2, del) = ui.input(|i| (
+            i.key_pressed(Key::ArrowUp),
+            i.key_pressed(Key::ArrowDown),
+            i.key_pressed(Key::ArrowLeft),
+            i.key_pressed(Key::ArrowRight),
+            i.key_pressed(Key::Enter),
+            i.key_pressed(Key::F2),
+            i.key_pressed(Key::Delete),
+        ));
+        let pos = self.index_window.selected.as_ref().and_then(|s| rows.iter().position(|r| &r.key == s));
+        let Some(pos) = pos else {
+            if up || down {
+                self.index_window.selected = Some(rows[0].key.clone());
+                self.index_window.scroll_to_selected = true;
+            }
+            return None;
+        };
+        let row = rows[pos].clone();
+        let folder = row.key.1.strip_suffix('/').map(|f| f.to_string());
+        let mut select = None;
+        let mut cmd = None;
+        if down {
+            select = Some((pos + 1).min(rows.len() - 1));
+        } else if up {
+            select = Some(pos.saturating_sub(1));
+        } else if right {
+            if row.can_open && !row.is_open {
+                self.index_window.toggle = Some((row.key.clone(), true));
+            } else if row.is_open {
+                select = Some((pos + 1).min(rows.len() - 1));
+            }
+        } else if left {
+            if row.can_open && row.is_open {
+                self.index_window.toggle = Some((row.key.clone(), false));
+            } else {
+                select = row.parent;
+            }
+        } else if enter {
+            if folder.is_some() {
+                self.index_window.toggle = Some((row.key.clone(), !row.is_open));
+            } else {
+                self.index_window.need_open = Some(row.key.1.clone());
+                cmd = Some(Command::OpenFile(row.key.1.clone()));
+            }
+        } else if f2 {
+            cmd = Some(match folder {
+                Some(folder) => Command::RenameFolder(folder),
+                None => Command::RenameFile(row.key.1.clone()),
+            });
+        } else if del {
+            self.index_window.delete_confirm = Some(row.key.1.clone());
+        }
+        if let Some(i) = select {
+            self.index_window.selected = Some(self.index_window.rows[i].key.clone());
+            self.index_window.scroll_to_selected = true;
+        }
+        cmd
+    }
+
     fn show_root_index(&mut self, config: &mut Config, ui: &mut Ui) -> Option<Command> {
-        self.show_sub_index(config, ui, ".", 0)
+        self.show_tree_options(config, ui);
+
+        //the tree takes keys when it has focus, arrows don't move the focus to other widgets
+        let tree_id = Self::tree_id();
+        ui.interact(ui.max_rect(), tree_id, Sense::focusable_noninteractive());
+        let mut key_cmd = None;
+        if ui.memory(|mem| mem.has_focus(tree_id)) {
+            let event_filter = EventFilter {
+                horizontal_arrows: true,
+                vertical_arrows: true,
+                ..Default::default()
+            };
+            ui.memory_mut(|mem| mem.set_focus_lock_filter(tree_id, event_filter));
+            key_cmd = self.tree_keys(ui);
+        }
+
+        self.index_window.rows.clear();
+        self.index_window.row_stack.clear();
+        let cmd = self.show_sub_index(config, ui, ".", "", &[]);
+        self.index_window.scroll_to_selected = false;
+        cmd.or(key_cmd)
     }
 
     pub fn show_index_window(&mut self, config: &mut Config, ui: &mut Ui, rect: Rect, outer_rect: Rect) -> Option<Command> {
@@ -307,7 +779,11 @@ impl NoteSpace {
             match Self::comfirm_window(ui, delete_confirm) {
                 Some(need_delete) => {
                     if need_delete {
-                        cmd = Some(Command::DeleteFile(delete_confirm.clone()));
+                        if let Some(folder) = delete_confirm.strip_suffix('/') {
+                            cmd = Some(Command::DeleteFolder(folder.to_string()));
+                        } else {
+                            cmd = Some(Command::DeleteFile(delete_confirm.clone()));
+                        }
                     }
                     self.index_window.delete_confirm = None;    //close comfirm window
                 }
@@ -353,8 +829,9 @@ impl NoteSpace {
 #[allow(dead_code)]
 #[derive(Clone,Debug)]
 pub struct DirNote {
-    deep: usize,
-    name: String,
+    pub deep: usize,
+    pub name: String,
+    pub is_back: bool,      //link back to an ancestor, a leaf of the cycle
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -420,12 +897,35 @@ impl CurFile {
     }
 }
 
+/// payload of a note dragged in index tree
+struct DragNote {
+    name: String,
+    parent: String,     //parent in tree, "" for root notes
+}
+
+/// top level dirs of the work dir which don't hold notes
+const RESERVED_DIRS: [&str; 1] = ["images"];
+/// a note with many parents lists at most this number of paths in path bar
+const MAX_LINK_PATHS: usize = 32;
+//partial paths kept in search of link paths, the notes with many ancestors may grow it fast
+const MAX_LINK_PATH_QUEUE: usize = 4096;
+/// last data_version of all workspaces
+static DATA_VERSION: AtomicUsize = AtomicUsize::new(0);
+
 pub struct NoteSpace {
     work_dir: PathBuf,
     files: Vec<PathBuf>,
+    folders: Vec<String>,
     file_links: HashMap<String, Vec<String>>,
+    tag_notes: HashMap<String, Vec<String>>,    //tag -> notes
     link_parents: HashMap<String, Vec<String>>,
-    directory: Vec<DirNote>,
+    roots: Vec<String>,     //notes without parent, and one note of each cycle which they don't reach
+    note_ids: NoteIds,      //id in frontmatter -> note name
+    data_version: usize,
+    index: NoteIndex,
+    trash: Trash,
+    history: NoteHistory,
+    git: Option<NoteRepo>,
     cur_file: Option<CurFile>,
     rename_window: RenameWin,
     index_window: IndexWind,
@@ -434,7 +934,15 @@ pub struct NoteSpace {
 /// rename window
 impl NoteSpace {
     pub fn rename_window_active(&mut self, name: &str) {
-        self.rename_window.active(name)
+        self.rename_window.active(name, false)
+    }
+
+    pub fn rename_window_active_folder(&mut self, folder: &str) {
+        self.rename_window.active(folder, true)
+    }
+
+    pub fn rename_is_folder(&self) -> bool {
+        self.rename_window.is_folder
     }
 
     pub fn rename_window_show(&mut self, ui: &mut Ui) -> bool {
@@ -447,31 +955,37 @@ impl NoteSpace {
 }
 
 impl NoteSpace {
-    pub fn new() -> Self {
+    pub fn new(work_dir: PathBuf) -> Self {
         let mut space = Self {
             work_dir: PathBuf::from("./note"),
             files: vec![],
+            folders: vec![],
             file_links: HashMap::new(),
+            tag_notes: HashMap::new(),
             link_parents: HashMap::new(),
-            directory: vec![],
+            roots: vec![],
+            note_ids: NoteIds::default(),
+            data_version: 0,
+            index: NoteIndex::new(),
+            trash: Trash::load(&work_dir),
+            history: NoteHistory::new(&work_dir),
+            git: NoteRepo::open(&work_dir),
             cur_file: None,
             rename_window: RenameWin::default(),
             index_window: IndexWind::default(),
         };
 
-        space.set_work_dir();
+        space.set_work_dir(work_dir);
+        space.index = NoteIndex::load(&space.index_file());
         space.flash_data();
         space
     }
 
-    fn set_work_dir(&mut self) {
-        let exe_path = std::env::current_exe().unwrap();
-        let mut parent_dir = exe_path.parent().map(|p| p.to_path_buf()).unwrap();
-        parent_dir.push("note");
-        self.work_dir = parent_dir;
+    fn set_work_dir(&mut self, work_dir: PathBuf) {
+        self.work_dir = work_dir;
 
         if std::fs::metadata(self.work_dir.clone()).is_err(){
-            let _= std::fs::create_dir(self.work_dir.clone());
+            let _= std::fs::create_dir_all(self.work_dir.clone());
         }
 
         if std::fs::metadata(self.image_path()).is_err(){
@@ -479,35 +993,135 @@ impl NoteSpace {
         }
     }
 
+    pub fn work_dir(&self) -> &PathBuf {
+        &self.work_dir
+    }
+
     fn set_files_in_word_dir(&mut self) {
         let mut paths = vec![];
-        if let Ok(dir) = fs::read_dir(self.work_dir.clone()) {
+        let mut folders = vec![];
+        Self::scan_dir(&self.work_dir.clone(), "", &mut paths, &mut folders);
+        folders.sort();
+        self.files = paths;
+        self.folders = folders;
+    }
+
+    //collect .md files and sub folders recursively, folder is relative to work dir
+    fn scan_dir(dir: &PathBuf, folder: &str, paths: &mut Vec<PathBuf>, folders: &mut Vec<String>) {
+        if let Ok(dir) = fs::read_dir(dir) {
             for entry in dir{
                 if let Ok(entry) = entry {
                     let path = entry.path();
+                    let file_name = entry.file_name().to_string_lossy().to_string();
                     if path.is_file() && path.extension().map_or(false, |e| e == "md") {
                         paths.push(path);
+                    } else if path.is_dir() {
+                        if file_name.starts_with('.') || (folder.is_empty() && RESERVED_DIRS.contains(&file_name.as_str())) {
+                            continue;
+                        }
+                        let sub_folder = Self::join_name(folder, &file_name);
+                        folders.push(sub_folder.clone());
+                        Self::scan_dir(&path, &sub_folder, paths, folders);
                     }
                 }
             }
         }
-        self.files = paths;
+    }
+
+    /// note name of a file in work dir, relative path without ".md", eg: "folder/note"
+    fn path2name(&self, path: &Path) -> String {
+        let mut file_name = path.to_path_buf();
+        file_name.set_extension("");
+        let rel = file_name.strip_prefix(&self.work_dir).unwrap_or(&file_name);
+        rel.to_string_lossy().replace("\\", "/")
+    }
+
+    /// "folder" + "note" => "folder/note"
+    pub fn join_name(folder: &str, name: &str) -> String {
+        if folder.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", folder, name)
+        }
+    }
+
+    /// "folder/note" => "folder"
+    pub fn folder_of(name: &str) -> String {
+        match name.rfind('/') {
+            Some(i) => name[..i].to_string(),
+            None => String::new(),
+        }
+    }
+
+    /// "folder/note" => "note"
+    pub fn base_name(name: &str) -> String {
+        match name.rfind('/') {
+            Some(i) => name[i+1..].to_string(),
+            None => name.to_string(),
+        }
+    }
+
+    //update index by modified time of files, only the changed files are parsed again
+    fn update_index(&mut self) {
+        let files: Vec<(String, PathBuf)> = self.files.iter()
+            .map(|file| (self.path2name(file), file.clone()))
+            .collect();
+        if self.index.update(&files) {
+            self.index.save(&self.index_file());
+        }
+    }
+
+    /// a copied note has the same id, the earliest created one keeps it
+    fn set_note_ids(&mut self) {
+        let mut ids = self.note_ids.borrow_mut();
+        ids.clear();
+        for file in &self.files {
+            let name = self.path2name(file);
+            let Some((_, id)) = self.index.properties(&name).into_iter().find(|(key, _)| key == "id") else {
+                continue;
+            };
+            let id = id.trim().to_string();
+            let created = |n: &String| (self.index.times(n).1, n.clone());
+            if ids.get(&id).is_some_and(|other| created(other) < created(&name)) {
+                continue;
+            }
+            ids.insert(id, name);
+        }
+    }
+
+    /// table of note ids, it's updated in flash_data
+    pub fn note_ids(&self) -> NoteIds {
+        self.note_ids.clone()
+    }
+
+    pub fn note_id(&self, name: &str) -> Option<String> {
+        self.note_ids.borrow().iter().find(|(_, n)| *n == name).map(|(id, _)| id.clone())
+    }
+
+    /// note name of link target, "[[id:...]]" is found by the id table
+    pub fn resolve_link(&self, target: &str) -> String {
+        match target.strip_prefix(ID_LINK_PREFIX) {
+            Some(id) => self.note_ids.borrow().get(id.trim()).cloned().unwrap_or(target.to_string()),
+            None => target.to_string(),
+        }
+    }
+
+    /// targets of links to the note, the name and "id:..."
+    pub fn link_targets(&self, name: &str) -> Vec<String> {
+        let mut targets = vec![name.to_string()];
+        if let Some(id) = self.note_id(name) {
+            targets.push(format!("{}{}", ID_LINK_PREFIX, id));
+        }
+        targets
     }
 
     //return map of file links
     fn set_file_links(&mut self) {
         let mut map: HashMap<String, Vec<String>> = HashMap::new();
         for file in &self.files {
-            let mut links = vec![];
-            if let Ok(s) = std::fs::read_to_string(file.clone()) {
-                let mut cfg = EditCfg::new(17.0, true, None);
-                let markdown = MarkDownImpl::new_simple(&s, &mut cfg);
-                links = markdown.markdown_get_links();
-            }
-            let mut file_name = file.clone();
-            file_name.set_extension("");
-            let file_name = file_name.file_name().unwrap().to_str().unwrap().to_string();
-            map.insert(file_name, links);
+            let name = self.path2name(file);
+            let links = self.index.links(&name).iter().map(|link| self.resolve_link(link)).collect();
+            map.insert(name, links);
         }
         self.file_links = map;
     }
@@ -529,68 +1143,260 @@ impl NoteSpace {
         self.link_parents = map;
     }
 
-    pub fn rebuild_directory(&mut self) {
-        let mut list = vec![];
-        for child in self.get_root_files() {
-            self.sub_directory(&child, 0, &mut list);
+    fn set_tag_notes(&mut self) {
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        for name in self.file_links.keys() {
+            for tag in self.index.tags(name) {
+                map.entry(tag).or_default().push(name.clone());
+            }
+        }
+        for notes in map.values_mut() {
+            notes.sort();
+        }
+        self.tag_notes = map;
+    }
+
+    /// all tags with count of notes, "a" is added for "a/b" and counts the notes of "a/b"
+    pub fn get_tags(&self) -> Vec<(String, usize)> {
+        let mut tags: Vec<String> = vec![];
+        for tag in self.tag_notes.keys() {
+            let mut end = tag.len();
+            loop {
+                let parent = tag[..end].to_string();
+                if !tags.contains(&parent) {
+                    tags.push(parent);
+                }
+                match tag[..end].rfind('/') {
+                    Some(i) => end = i,
+                    None => break,
+                }
+            }
+        }
+        tags.sort();
+        tags.into_iter()
+            .map(|tag| {
+                let count = self.get_tag_notes(&tag).len();
+                (tag, count)
+            })
+            .collect()
+    }
+
+    /// notes with the tag or its nested tags
+    pub fn get_tag_notes(&self, tag: &str) -> Vec<String> {
+        let prefix = format!("{}/", tag);
+        let mut notes: Vec<String> = self.tag_notes.iter()
+            .filter(|(t, _)| *t == tag || t.starts_with(&prefix))
+            .flat_map(|(_, notes)| notes.iter().cloned())
+            .collect();
+        notes.sort();
+        notes.dedup();
+        notes
+    }
+
+    /// notes in the order of index tree, Manual keeps the order
+    pub fn sort_notes(&self, notes: &mut [String], sort: TreeSort) {
+        match sort {
+            TreeSort::Manual => {}
+            TreeSort::Name => notes.sort_by_cached_key(|n| Self::base_name(n).to_lowercase()),
+            TreeSort::Modified => notes.sort_by_key(|n| Reverse(self.index.times(n).0)),
+            TreeSort::Created => notes.sort_by_key(|n| Reverse(self.index.times(n).1)),
         }
-        self.directory = list;
     }
 
-    fn sub_directory(&self, name: &str, deep: usize, list: &mut Vec<DirNote>) {
-        if deep > 5 {
+    /// notes whose base name contains the filter, and all their ancestors
+    pub fn filter_tree(&self, filter: &str) -> HashSet<String> {
+        let filter = filter.trim().to_lowercase();
+        let mut visible = HashSet::new();
+        let mut stack: Vec<String> = self.get_all_notes().into_iter()
+            .filter(|n| Self::base_name(n).to_lowercase().contains(&filter))
+            .collect();
+        while let Some(name) = stack.pop() {
+            if visible.insert(name.clone()) {
+                stack.extend(self.get_parents(&name));
+            }
+        }
+        visible
+    }
+
+    /// rebuild the shown notes when filter or links are changed
+    fn update_filter(&mut self) {
+        let key = (self.index_window.filter.trim().to_string(), self.data_version);
+        if key == self.index_window.filter_key {
             return;
         }
-        list.push(DirNote{
-            deep,
-            name: name.to_string()});
+        self.index_window.visible = if key.0.is_empty() {None} else {Some(self.filter_tree(&key.0))};
+        self.index_window.filter_key = key;
+    }
+
+    pub fn set_tag_filter(&mut self, tag: Option<String>) {
+        self.index_window.tag_filter = tag;
+    }
+
+    pub fn tag_filter(&self) -> Option<&String> {
+        self.index_window.tag_filter.as_ref()
+    }
 
-        for child in self.get_child_links(name) {
-            self.sub_directory(&child, deep+1, list);
+    /// root and the notes under it in the link tree, "." for all roots
+    /// a note with several parents is in it more than once, but only the first one is expanded
+    /// a link back to its ancestor is a leaf with is_back
+    pub fn link_tree(&self, root: &str) -> Vec<DirNote> {
+        let mut list = vec![];
+        let mut expanded = HashSet::new();
+        let mut stack: Vec<(String, usize, Vec<String>)> = if root == "." {
+            self.get_root_files().into_iter().rev().map(|name| (name, 0, vec![])).collect()
+        } else {
+            vec![(root.to_string(), 0, vec![])]
+        };
+        while let Some((name, deep, mut ancestors)) = stack.pop() {
+            if ancestors.contains(&name) {
+                list.push(DirNote { deep, name, is_back: true });
+                continue;
+            }
+            list.push(DirNote { deep, name: name.clone(), is_back: false });
+            if !expanded.insert(name.clone()) {
+                continue;
+            }
+            ancestors.push(name.clone());
+            for child in self.get_child_links(&name).into_iter().rev() {
+                stack.push((child, deep + 1, ancestors.clone()));
+            }
         }
+        list
     }
 
     pub fn flash_data(&mut self) {
+        //increased across workspaces, views of the old workspace are rebuilt after switching
         self.set_files_in_word_dir();
+        self.update_index();
+        self.set_note_ids();
         self.set_file_links();
         self.set_link_parents();
-        self.rebuild_directory();
+        self.set_roots();
+        self.set_tag_notes();
+        self.data_version = DATA_VERSION.fetch_add(1, Ordering::Relaxed) + 1;
+    }
+
+    /// changed after every flash_data, views built from the links can check it to rebuild
+    pub fn data_version(&self) -> usize {
+        self.data_version
     }
 
+    /// the first path of get_link_paths
     pub fn get_path_from_link_parents(&self, name: &str) -> Vec<String> {
-        let mut paths = vec![];
-        let mut cur_name = name.to_string();
+        self.get_link_paths(name).into_iter().next().unwrap_or_else(|| vec![name.to_string()])
+    }
 
-        loop {
-            if paths.contains(&cur_name) {
+    /// paths from roots to the note through every parent, at most MAX_LINK_PATHS, shorter first
+    /// a path starts at a root, or at the note whose parents are all in the path (a cycle)
+    /// breadth first, so the shortest paths are found before the limit
+    pub fn get_link_paths(&self, name: &str) -> Vec<Vec<String>> {
+        let mut paths = vec![];
+        let mut queue = VecDeque::from([vec![name.to_string()]]);
+        while let Some(path) = queue.pop_front() {
+            if paths.len() >= MAX_LINK_PATHS {
                 break;
             }
-            paths.insert(0, cur_name.clone());
-            if let Some(parents) = self.link_parents.get(&cur_name) {
-                if let Some(parent) = parents.first() {
-                    cur_name = parent.clone();
-                } else {
+            let mut parents = self.link_parents.get(&path[0]).cloned().unwrap_or_default();
+            parents.retain(|p| !path.contains(p));
+            if parents.is_empty() {
+                paths.push(path);
+                continue;
+            }
+            parents.sort();
+            for parent in parents {
+                if queue.len() >= MAX_LINK_PATH_QUEUE {
                     break;
                 }
-            } else {
-                break;
+                let mut new_path = vec![parent];
+                new_path.extend(path.iter().cloned());
+                queue.push_back(new_path);
             }
         }
-
         paths
     }
 
     fn get_root_files(&self) -> Vec<String> {
-        let mut roots = vec![];
-        for (file, _) in &self.file_links {
-            if None == self.link_parents.get(file) {
-                roots.push(file.clone());
+        self.roots.clone()
+    }
+
+    //notes without parent, then one note of each cycle not reached from them
+    fn set_roots(&mut self) {
+        let notes = self.get_all_notes();
+        let mut roots: Vec<String> = notes.iter().filter(|n| !self.link_parents.contains_key(*n)).cloned().collect();
+        let mut reached: HashSet<String> = HashSet::new();
+        let mut stack = roots.clone();
+        loop {
+            while let Some(name) = stack.pop() {
+                if reached.insert(name.clone()) {
+                    stack.extend(self.get_child_links(&name));
+                }
             }
+            let Some(first) = notes.iter().find(|n| !reached.contains(*n)) else {
+                break;
+            };
+            //parents of a note not reached are not reached too, going up ends in a cycle
+            let mut up = vec![first.clone()];
+            let root = loop {
+                let Some(parent) = self.get_parents(&up[up.len() - 1]).into_iter().min() else {
+                    break first.clone();
+                };
+                if up.contains(&parent) {
+                    break parent;
+                }
+                up.push(parent);
+            };
+            roots.push(root.clone());
+            stack.push(root);
         }
         roots.sort();
+        self.roots = roots;
+    }
+
+    /// link roots which are stored in the folder, "" is the top folder
+    pub fn get_folder_root_files(&self, folder: &str) -> Vec<String> {
+        let mut roots = self.get_root_files();
+        roots.retain(|name| Self::folder_of(name) == folder);
         roots
     }
 
+    /// direct sub folders of the folder, "" is the top folder
+    pub fn get_sub_folders(&self, folder: &str) -> Vec<String> {
+        self.folders.iter()
+            .filter(|f| Self::folder_of(f) == folder)
+            .cloned()
+            .collect()
+    }
+
+    /// all folders, "" is the top folder
+    pub fn get_folders(&self) -> Vec<String> {
+        let mut folders = vec![String::new()];
+        folders.extend(self.folders.iter().cloned());
+        folders
+    }
+
+    pub fn is_folder_exist(&self, folder: &str) -> bool {
+        folder.is_empty() || self.folders.iter().any(|f| f == folder)
+    }
+
+    /// all notes in the folder and its sub folders
+    pub fn get_notes_in_folder(&self, folder: &str) -> Vec<String> {
+        let prefix = format!("{}/", folder);
+        let mut notes: Vec<String> = self.file_links.keys()
+            .filter(|name| name.starts_with(&prefix))
+            .cloned()
+            .collect();
+        notes.sort();
+        notes
+    }
+
+    /// name is a note in work dir, not a path in file system
+    pub fn is_note_name(&self, name: &str) -> bool {
+        if name.contains('\\') || name.contains(':') || name.starts_with('/') || name.starts_with("./") || name.starts_with("../") {
+            return false;
+        }
+        !name.contains('/') || self.is_file_exist(name) || self.is_folder_exist(&Self::folder_of(name))
+    }
+
     pub fn note_name_to_curfile(&self, name: &str) -> CurFile {
         let path = "./".to_string() + &self.get_path_from_link_parents(name).join("/");
         CurFile::Note(FilePath{
@@ -648,6 +1454,31 @@ impl NoteSpace {
         vec![]
     }
 
+    /// names of all notes in work dir
+    pub fn get_all_notes(&self) -> Vec<String> {
+        let mut notes: Vec<String> = self.file_links.keys().cloned().collect();
+        notes.sort();
+        notes
+    }
+
+    /// note is linked from ancestor directly or indirectly
+    pub fn is_descendant(&self, note: &str, ancestor: &str) -> bool {
+        let mut visited = vec![ancestor.to_string()];
+        let mut stack = vec![ancestor.to_string()];
+        while let Some(name) = stack.pop() {
+            for child in self.get_child_links(&name) {
+                if child == note {
+                    return true;
+                }
+                if !visited.contains(&child) {
+                    visited.push(child.clone());
+                    stack.push(child);
+                }
+            }
+        }
+        false
+    }
+
     pub fn get_parents(&self, name: &str) -> Vec<String> {
         if let Some(links) = self.link_parents.get(name).cloned() {
             return links;
@@ -664,25 +1495,117 @@ impl NoteSpace {
         std::fs::metadata(path).is_ok()
     }
 
-    pub fn new_file_name(&self) -> Option<String> {
-        for i in 1..999 {
-            let name = format!("untitled_{}", i);
-            if self.is_file_exist(&name) == false {
-                return Some(name);
-            } 
+    /// untitled_N in folder, it's renamed by the first heading later
+    pub fn new_file_name(&self, folder: &str) -> String {
+        let mut i = 1;
+        loop {
+            let name = Self::join_name(folder, &format!("untitled_{}", i));
+            if !self.is_file_exist(&name) {
+                return name;
+            }
+            i += 1;
         }
-        None
+    }
+
+    /// name itself if not exist, or name_1, name_2 ...
+    pub fn unique_name(&self, name: &str) -> String {
+        if !self.is_file_exist(name) {
+            return name.to_string();
+        }
+        let mut i = 1;
+        loop {
+            let new_name = format!("{}_{}", name, i);
+            if !self.is_file_exist(&new_name) {
+                return new_name;
+            }
+            i += 1;
+        }
+    }
+
+    pub fn folder2path(&self, folder: &str) -> String {
+        format!("{}/{}", &self.work_dir.display(), folder)
+    }
+
+    fn create_parent_dir(path: &str) -> std::io::Result<()> {
+        if let Some(parent) = PathBuf::from(path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(())
+    }
+
+    pub fn new_folder_name(&self, parent: &str) -> String {
+        let mut i = 1;
+        loop {
+            let name = Self::join_name(parent, &format!("new_folder_{}", i));
+            if std::fs::metadata(self.folder2path(&name)).is_err() {
+                return name;
+            }
+            i += 1;
+        }
+    }
+
+    pub fn create_folder(&self, folder: &str) -> std::io::Result<()> {
+        std::fs::create_dir_all(self.folder2path(folder))
+    }
+
+    pub fn rename_folder(&self, org: &str, new: &str) -> std::io::Result<()> {
+        let to = self.folder2path(new);
+        Self::create_parent_dir(&to)?;
+        std::fs::rename(self.folder2path(org), to)
+    }
+
+    pub fn delete_folder(&self, folder: &str) -> std::io::Result<()> {
+        std::fs::remove_dir_all(self.folder2path(folder))
     }
 
     pub fn rename(&self, org: &str, new: &str) -> std::io::Result<()> {
         let from = self.name2path(org);
         let to = self.name2path(new);
+        Self::create_parent_dir(&to)?;
         std::fs::rename(from, to)
     }
 
-    pub fn delete_file(&self, file: &str) -> std::io::Result<()> {
+    /// move the note to trash, with the links removed from parents
+    pub fn delete_file(&mut self, file: &str, parents: Vec<ParentLink>) -> std::io::Result<()> {
         let from = self.name2path(file);
-        std::fs::remove_file(from)
+        self.trash.put(Path::new(&from), file, parents)
+    }
+
+    pub fn history(&self) -> &NoteHistory {
+        &self.history
+    }
+
+    /// git repository of work dir, None if not in a repository
+    pub fn git(&self) -> Option<&NoteRepo> {
+        self.git.as_ref()
+    }
+
+    pub fn git_init(&mut self) -> std::io::Result<()> {
+        self.git = Some(NoteRepo::init(&self.work_dir)?);
+        Ok(())
+    }
+
+    pub fn trash(&self) -> &Trash {
+        &self.trash
+    }
+
+    /// move the note back from trash, it gets a new name if the name is used
+    pub fn restore_from_trash(&mut self, id: &str) -> std::io::Result<TrashItem> {
+        let Some(item) = self.trash.items().iter().find(|item| item.id == id) else {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, id));
+        };
+        let name = self.unique_name(&item.name);
+        let mut item = self.trash.restore(id, Path::new(&self.name2path(&name)))?;
+        item.name = name;
+        Ok(item)
+    }
+
+    /// delete permanently, all notes in trash if id is None
+    pub fn purge_trash(&mut self, id: Option<&str>) -> std::io::Result<()> {
+        match id {
+            Some(id) => self.trash.purge(id),
+            None => self.trash.purge_all(),
+        }
     }
 
     pub fn read_note(&self, name: &str) -> std::io::Result<String> {
@@ -690,8 +1613,29 @@ impl NoteSpace {
         std::fs::read_to_string(path)
     }
 
+    /// text of each line as shown in editor, the index is the line_no of editor
+    /// frontmatter isn't in the lines of editor
+    pub fn text_to_lines(text: &str) -> Vec<String> {
+        let (_, body) = FrontMatter::split(text);
+        let cfg = EditCfg::new(17.0, true, None);
+        let markdown = MarkDownImpl::new_simple(body, &cfg);
+        markdown.markdown_to_pgh_texts().iter().map(|p| p.get_text()).collect()
+    }
+
+    /// line_no in editor of the text at offset, None if it's in frontmatter
+    /// the lines before it are counted, so the same lines are not mixed up
+    pub fn offset_to_line_no(text: &str, offset: usize) -> Option<usize> {
+        let (_, body) = FrontMatter::split(text);
+        if offset < text.len() - body.len() {
+            return None;
+        }
+        let line_end = text[offset..].find('\n').map_or(text.len(), |i| offset + i);
+        Some(Self::text_to_lines(&text[..line_end]).len() - 1)
+    }
+
     pub fn write_note(&self, name: &str, text: &str) -> std::io::Result<()> {
         let path = self.name2path(name);
+        Self::create_parent_dir(&path)?;
         std::fs::write(path, text)
     }
 
@@ -703,6 +1647,21 @@ impl NoteSpace {
         format!("{}/{}", &self.work_dir.display(), "config.json")
     }
 
+    pub fn index_file(&self) -> String {
+        format!("{}/{}", &self.work_dir.display(), "index.json")
+    }
+
+    pub fn index(&self) -> &NoteIndex {
+        &self.index
+    }
+
+    /// (key, value) in frontmatter of the note
+    pub fn get_properties(&self, name: &str) -> Vec<(String, String)> {
+        self.index.properties(name)
+    }
+
+
+
     pub fn image_path(&self) -> String {
         let path = format!("{}/{}", &self.work_dir.display(), "images");
         path.replace("\\", "/")
@@ -711,7 +1670,7 @@ impl NoteSpace {
     #[allow(dead_code)]
     pub fn get_root_markdown_text(&self) -> String {
         let mut rs = "".to_string();
-        for n in &self.directory {
+        for n in &self.link_tree(".") {
             let mut node_s = "".to_string();
             for _ in 0..=n.deep {
                 node_s += "==";
@@ -724,3 +1683,106 @@ impl NoteSpace {
 
 
 }
+
+
+#[test]
+pub fn test_note_name_folder() {
+    assert_eq!(NoteSpace::join_name("", "note"), "note");
+    assert_eq!(NoteSpace::join_name("a/b", "note"), "a/b/note");
+    assert_eq!(NoteSpace::folder_of("a/b/note"), "a/b");
+    assert_eq!(NoteSpace::folder_of("note"), "");
+    assert_eq!(NoteSpace::base_name("a/b/note"), "note");
+    assert_eq!(NoteSpace::base_name("note"), "note");
+}
+
+#[test]
+pub fn test_note_id_links() {
+    let dir = std::env::temp_dir().join(format!("egscribe_ids_{}", std::process::id()));
+    let _ = std::fs::create_dir_all(dir.join("f"));
+    std::fs::write(dir.join("f/a.md"), "---\nid: 0190-abc\n---\n# A\n").unwrap();
+    std::fs::write(dir.join("b.md"), "[[id:0190-abc]]\n").unwrap();
+    //a copy of the note has the same id
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    std::fs::write(dir.join("0copy.md"), "---\nid: 0190-abc\n---\n# A\n").unwrap();
+
+    let space = NoteSpace::new(dir.clone());
+    assert_eq!(space.note_id("f/a"), Some("0190-abc".to_string()));
+    assert_eq!(space.resolve_link("id:0190-abc"), "f/a");
+    assert_eq!(space.get_child_links("b"), vec!["f/a"]);
+    assert_eq!(space.get_parents("f/a"), vec!["b"]);
+    assert_eq!(space.link_targets("f/a"), vec!["f/a", "id:0190-abc"]);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+pub fn test_link_cycles() {
+    let dir = std::env::temp_dir().join(format!("egscribe_cycles_{}", std::process::id()));
+    let _ = std::fs::create_dir_all(&dir);
+    std::fs::write(dir.join("r.md"), "[[a]]\n[[b]]\n").unwrap();
+    std::fs::write(dir.join("a.md"), "[[c]]\n").unwrap();
+    std::fs::write(dir.join("b.md"), "[[c]]\n").unwrap();
+    std::fs::write(dir.join("c.md"), "[[a]]\n").unwrap();
+    //diamonds: every d has two paths from the one above
+    for i in 0..16 {
+        std::fs::write(dir.join(format!("d{}.md", i)), format!("[[x{}]]\n[[y{}]]\n", i, i)).unwrap();
+        std::fs::write(dir.join(format!("x{}.md", i)), format!("[[d{}]]\n", i + 1)).unwrap();
+        std::fs::write(dir.join(format!("y{}.md", i)), format!("[[d{}]]\n", i + 1)).unwrap();
+    }
+
+    let space = NoteSpace::new(dir.clone());
+    assert_eq!(space.link_tree("d0").len(), 16 * 4 + 1);
+    let tree: Vec<String> = space.link_tree("r").iter()
+        .map(|n| format!("{}{}{}", n.deep, n.name, if n.is_back {"^"} else {""}))
+        .collect();
+    assert_eq!(tree, vec!["0r", "1a", "2c", "3a^", "1b", "2c"]);
+    assert_eq!(space.get_link_paths("c"), vec![vec!["r", "a", "c"], vec!["r", "b", "c"]]);
+    assert_eq!(space.get_path_from_link_parents("a"), vec!["r", "a"]);
+    //the short path comes first, though the long ones are more than the limit
+    std::fs::write(dir.join("z.md"), "").unwrap();
+    std::fs::write(dir.join("d16.md"), "[[z]]\n").unwrap();
+    std::fs::write(dir.join("zz.md"), "[[z]]\n").unwrap();
+    let space = NoteSpace::new(dir.clone());
+    let paths = space.get_link_paths("z");
+    assert_eq!(paths.len(), MAX_LINK_PATHS);
+    assert_eq!(paths[0], vec!["zz", "z"]);
+    assert!(paths.windows(2).all(|w| w[0].len() <= w[1].len()));
+    std::fs::remove_file(dir.join("d16.md")).unwrap();
+    std::fs::remove_file(dir.join("zz.md")).unwrap();
+    std::fs::remove_file(dir.join("z.md")).unwrap();
+
+    //a cycle without outside parent is shown from one of it
+    std::fs::write(dir.join("p.md"), "[[q]]\n").unwrap();
+    std::fs::write(dir.join("q.md"), "[[p]]\n[[s]]\n").unwrap();
+    std::fs::write(dir.join("s.md"), "").unwrap();
+    let space = NoteSpace::new(dir.clone());
+    let roots = space.get_root_files();
+    assert!(roots.contains(&"p".to_string()) && !roots.contains(&"q".to_string()) && !roots.contains(&"s".to_string()));
+    let tree: Vec<String> = space.link_tree("p").iter()
+        .map(|n| format!("{}{}{}", n.deep, n.name, if n.is_back {"^"} else {""}))
+        .collect();
+    assert_eq!(tree, vec!["0p", "1q", "2p^", "2s"]);
+    assert!(space.link_tree(".").iter().any(|n| n.name == "q"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+pub fn test_tree_filter_sort() {
+    let dir = std::env::temp_dir().join(format!("egscribe_tree_{}", std::process::id()));
+    let _ = std::fs::create_dir_all(&dir);
+    std::fs::write(dir.join("r.md"), "[[b]]\n[[A]]\n").unwrap();
+    std::fs::write(dir.join("A.md"), "[[c]]\n").unwrap();
+    std::fs::write(dir.join("b.md"), "").unwrap();
+    std::fs::write(dir.join("c.md"), "").unwrap();
+    std::fs::write(dir.join("x.md"), "").unwrap();
+
+    let space = NoteSpace::new(dir.clone());
+    let mut visible: Vec<String> = space.filter_tree(" C ").into_iter().collect();
+    visible.sort();
+    assert_eq!(visible, vec!["A", "c", "r"]);
+    let mut childs = space.get_child_links("r");
+    space.sort_notes(&mut childs, TreeSort::Manual);
+    assert_eq!(childs, vec!["b", "A"]);
+    space.sort_notes(&mut childs, TreeSort::Name);
+    assert_eq!(childs, vec!["A", "b"]);
+    let _ = std::fs::remove_dir_all(&dir);
+}
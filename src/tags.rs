@@ -0,0 +1,68 @@
+use eframe::egui::{self, Button, Color32, RichText, ScrollArea, Widget, Window};
+use crate::medit::{Command, IconName};
+use crate::space::NoteSpace;
+use crate::ToolBar;
+
+/// list of tags with count of notes, click to filter the index tree
+pub struct TagsPanel {
+    data_version: usize,
+    tags: Vec<(String, usize)>,
+}
+
+impl TagsPanel {
+    pub fn new() -> Self {
+        Self {
+            data_version: 0,
+            tags: vec![],
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, note_space: &NoteSpace, is_show: &mut bool) -> Option<Command> {
+        let mut cmd = None;
+        if !*is_show {
+            return cmd;
+        }
+        if self.data_version != note_space.data_version() {
+            self.tags = note_space.get_tags();
+            self.data_version = note_space.data_version();
+        }
+
+        let filter = note_space.tag_filter();
+        let mut open = true;
+        Window::new("tags")
+            .default_size([240.0, 360.0])
+            .title_bar(false)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ToolBar::tool_icon_button(ui, IconName::icon_close, false, false, "Close").clicked() {
+                        *is_show = false;
+                    }
+                    ui.label(format!("Tags: {}", self.tags.len()));
+                });
+                ui.separator();
+
+                ScrollArea::both().auto_shrink(false).show(ui, |ui| {
+                    for (tag, count) in &self.tags {
+                        //nested tag is indented under its parent
+                        let deep = tag.matches('/').count();
+                        let base_name = NoteSpace::base_name(tag);
+                        ui.horizontal(|ui| {
+                            ui.add_space(deep as f32 * 16.0);
+                            let is_selected = filter == Some(tag);
+                            let text = RichText::new(format!("#{}", base_name)).color(ui.visuals().hyperlink_color);
+                            let r = Button::new(text).fill(Color32::TRANSPARENT).selected(is_selected).ui(ui);
+                            if r.on_hover_text(format!("#{}", tag)).clicked() {
+                                cmd = Some(Command::FilterTag(if is_selected {None} else {Some(tag.clone())}));
+                            }
+                            ui.weak(count.to_string());
+                        });
+                    }
+                    if self.tags.is_empty() {
+                        ui.weak("No tags");
+                    }
+                });
+            });
+        cmd
+    }
+}
@@ -0,0 +1,202 @@
+use std::fmt::Write;
+use chrono::{Local, NaiveDateTime};
+use eframe::egui::{Order, Rect, Ui, Vec2, Window};
+use regex::Regex;
+use crate::medit::FrontMatter;
+use crate::space::NoteSpace;
+
+/// notes in this folder of work dir are templates
+pub const TEMPLATE_FOLDER: &str = "templates";
+/// where the cursor is placed in new note
+const CURSOR_VAR: &str = "{{cursor}}";
+/// frontmatter key of template for the pattern of new note name
+const FILENAME_KEY: &str = "filename";
+/// a long heading is cut to this length for the note name
+const TITLE_NAME_LEN: usize = 80;
+
+pub struct TemplateVars {
+    pub date: NaiveDateTime,
+    pub title: String,
+    pub parent: String,
+}
+
+impl TemplateVars {
+    pub fn new(parent: Option<&String>) -> Self {
+        Self {
+            date: Local::now().naive_local(),
+            title: String::new(),
+            parent: parent.cloned().unwrap_or_default(),
+        }
+    }
+}
+
+pub struct Expanded {
+    pub text: String,
+    pub cursor_line: Option<usize>,     //line_no in editor
+}
+
+/// text of a chrono format, None if the format of user is invalid, eg: "%Q"
+pub fn checked_format(format: impl std::fmt::Display) -> Option<String> {
+    let mut s = String::new();
+    write!(s, "{}", format).ok()?;
+    Some(s)
+}
+
+/// replace {{date}}, {{time}}, {{title}}, {{parent}}, and {{date:%Y%m%d}} with format
+/// unknown variables are kept
+pub fn expand_vars(s: &str, vars: &TemplateVars) -> String {
+    let re = Regex::new(r"\{\{\s*(\w+)\s*(?::([^}]*))?\}\}").unwrap();
+    re.replace_all(s, |cap: &regex::Captures| {
+        let format = cap.get(2).map(|f| f.as_str().trim());
+        match &cap[1] {
+            //invalid format is kept as raw text
+            "date" => checked_format(vars.date.format(format.unwrap_or("%Y-%m-%d"))).unwrap_or_else(|| cap[0].to_string()),
+            "time" => checked_format(vars.date.format(format.unwrap_or("%H:%M"))).unwrap_or_else(|| cap[0].to_string()),
+            "title" => vars.title.clone(),
+            "parent" => vars.parent.clone(),
+            _ => cap[0].to_string(),
+        }
+    }).to_string()
+}
+
+/// name of new note from the "filename" in frontmatter of template
+pub fn filename_pattern(template: &str) -> Option<String> {
+    let front = FrontMatter::parse(template)?;
+    front.get(FILENAME_KEY).map(|p| p.value.clone()).filter(|p| !p.is_empty())
+}
+
+/// name of new note from the "filename" pattern, None if it's empty, eg: "{{title}}" before the title is known
+pub fn pattern_name(pattern: &str, vars: &TemplateVars) -> Option<String> {
+    Some(sanitize_name(&expand_vars(pattern, vars))).filter(|name| !name.is_empty())
+}
+
+/// chars can't be used in file name are replaced by "-", "/" is kept for sub folder
+pub fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if "\\:*?\"<>|".contains(c) || c.is_control() {'-'} else {c})
+        .collect::<String>()
+        .split('/')
+        .map(|s| s.trim().trim_matches('.'))
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// name of note from its first heading, "/" and the chars of link syntax are replaced too
+pub fn title_name(title: &str) -> String {
+    let name: String = title.chars()
+        .map(|c| if "/#[]^".contains(c) {'-'} else {c})
+        .take(TITLE_NAME_LEN)
+        .collect();
+    sanitize_name(&name)
+}
+
+/// text of new note, the "filename" of frontmatter is removed
+pub fn render(template: &str, vars: &TemplateVars) -> Expanded {
+    let (front, body) = FrontMatter::split(template);
+    let front_text = match front {
+        Some(mut front) => {
+            if let Some(i) = front.props.iter().position(|p| p.key == FILENAME_KEY) {
+                front.remove(i);
+            }
+            if front.props.is_empty() {
+                String::new()
+            } else {
+                front.to_text()
+            }
+        }
+        None => String::new(),
+    };
+    let text = expand_vars(&(front_text + body), vars);
+
+    let cursor_line = NoteSpace::text_to_lines(&text).iter().position(|line| line.contains(CURSOR_VAR));
+    Expanded {
+        text: text.replacen(CURSOR_VAR, "", 1),
+        cursor_line,
+    }
+}
+
+/// window to pick a template for new note
+pub struct TemplateWin {
+    is_show: bool,
+    parent: Option<String>,
+    folder: String,
+    templates: Vec<String>,
+}
+
+impl TemplateWin {
+    pub fn default() -> Self {
+        Self {
+            is_show: false,
+            parent: None,
+            folder: String::new(),
+            templates: vec![],
+        }
+    }
+
+    pub fn active(&mut self, parent: Option<String>, folder: &str, templates: Vec<String>) {
+        self.is_show = true;
+        self.parent = parent;
+        self.folder = folder.to_string();
+        self.templates = templates;
+    }
+
+    //return (parent, folder, template) when one is picked, template is None for empty note
+    pub fn show(&mut self, ui: &mut Ui) -> Option<(Option<String>, String, Option<String>)> {
+        if !self.is_show {
+            return None;
+        }
+
+        let size = Vec2::new(200.0, 30.0);
+        let mut rect = Rect::from_min_size(ui.cursor().left_top(), size);
+        if let Some(pointer_pos) = ui.ctx().pointer_interact_pos() {
+            rect.min = pointer_pos;
+        }
+        let mut picked = None;
+        let egui_ctx = ui.ctx();
+        Window::new("new note")
+            .default_rect(rect)
+            .open(&mut self.is_show)
+            .resizable([false, false])
+            .order(Order::TOP)
+            .show(egui_ctx, |ui| {
+                if ui.button("Empty note").clicked() {
+                    picked = Some(None);
+                }
+                ui.separator();
+                for template in &self.templates {
+                    let name = template.strip_prefix(&format!("{}/", TEMPLATE_FOLDER)).unwrap_or(template);
+                    if ui.button(name).clicked() {
+                        picked = Some(Some(template.clone()));
+                    }
+                }
+            });
+
+        let template = picked?;
+        self.is_show = false;
+        Some((self.parent.take(), self.folder.clone(), template))
+    }
+}
+
+#[test]
+pub fn test_template() {
+    let mut vars = TemplateVars::new(Some(&"home".to_string()));
+    vars.date = chrono::NaiveDate::from_ymd_opt(2025, 2, 4).unwrap().and_hms_opt(9, 5, 0).unwrap();
+    vars.title = "note".to_string();
+    assert_eq!(expand_vars("{{date}} {{time}} {{ date:%d/%m }} {{title}} {{parent}} {{other}}", &vars),
+        "2025-02-04 09:05 04/02 note home {{other}}");
+    assert_eq!(expand_vars("{{date:%Q}} {{time:%Z}}", &vars), "{{date:%Q}} {{time:%Z}}");
+
+    let template = "---\nfilename: meeting-{{date}}\ntags: [meeting]\n---\n\n# {{title}}\n\n- {{cursor}}\n";
+    assert_eq!(filename_pattern(template), Some("meeting-{{date}}".to_string()));
+    assert_eq!(pattern_name("meeting-{{date}}", &vars), Some("meeting-2025-02-04".to_string()));
+    vars.title = String::new();
+    assert_eq!(pattern_name("{{title}}", &vars), None);
+    vars.title = "note".to_string();
+    let expanded = render(template, &vars);
+    assert_eq!(expanded.text, "---\ntags: [meeting]\n---\n\n# note\n\n- \n");
+    assert_eq!(expanded.cursor_line, Some(1));
+
+    assert_eq!(sanitize_name("a:b/ c?/../d"), "a-b/c-/d");
+    assert_eq!(title_name("Plan: a/b [[x]] #1?"), "Plan- a-b --x-- -1-");
+}
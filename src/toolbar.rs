@@ -1,10 +1,11 @@
 use core::f32;
 use std::sync::Arc;
-use eframe::egui::{Button, Color32, FontId, Galley, Rect, Response, Sense, Ui, Visuals, Widget};
+use eframe::egui::{Button, Checkbox, Color32, DragValue, FontId, Galley, Rect, Response, Sense, Ui, Visuals, Widget};
 
 use crate::medit::{IconName, PghText};
 use crate::mem::Store;
 use crate::space::CurFile;
+use crate::periodic::Period;
 
 pub enum ToolBarType {
     PathBar(String),
@@ -35,12 +36,18 @@ impl ToolBar<'_> {
         PghText::text_galley(ui, String::from(text), fg, 320.0)
     }
 
-    fn sub_menus(store: &mut Store, ui: &mut Ui, name: &str, deep: usize) {
-        if deep > 5 {
+    /// ancestors: the notes above name in menus, a link back to them is a leaf
+    fn sub_menus(store: &mut Store, ui: &mut Ui, name: &str, ancestors: &[String]) {
+        Self::set_ui_button_font(ui);
+        if ancestors.iter().any(|a| a == name) {
+            let text = format!("\u{21bb} {}", name);
+            if ui.button(Self::button_galley(ui, &text, Some(ui.visuals().weak_text_color()))).clicked() {
+                ui.close_menu();
+                let _ = store.open(name);
+            }
             return;
         }
-        Self::set_ui_button_font(ui);
-        
+
         let childs = store.note_space.get_child_links(name);
         if childs.len() == 0 {
             if ui.button(Self::button_galley(ui, name, None)).clicked() {
@@ -51,8 +58,10 @@ impl ToolBar<'_> {
             let rsp = ui.menu_button(
                 Self::button_galley(ui, name, None), 
                 |ui|{
+                    let mut child_ancestors = ancestors.to_vec();
+                    child_ancestors.push(name.to_string());
                     for c in childs {
-                        Self::sub_menus(store, ui, &c, deep+1);
+                        Self::sub_menus(store, ui, &c, &child_ancestors);
                     }
                     ui.separator();
                     if ui.button(Self::button_galley(ui, "+", None)).clicked() {
@@ -70,8 +79,9 @@ impl ToolBar<'_> {
 
     fn pop_note_dir_menus(store: &mut Store, ui: &mut Ui, name: &str) {
         let childs = store.note_space.get_child_links(name);
+        let ancestors = if name == "." {vec![]} else {vec![name.to_string()]};
         for c in childs {
-            Self::sub_menus(store, ui, &c, 0);
+            Self::sub_menus(store, ui, &c, &ancestors);
         }
         ui.separator();
         if ui.button(Self::button_galley(ui, "+", None)).clicked() {
@@ -110,6 +120,64 @@ impl ToolBar<'_> {
         }
     }
 
+    fn git_menus(store: &mut Store, ui: &mut Ui) {
+        if store.note_space.git().is_none() {
+            if ui.button("Init repository").on_hover_text("Create a git repository in the workspace").clicked() {
+                ui.close_menu();
+                let _ = store.git_init();
+            }
+            return;
+        }
+        if ui.button("Commit now").clicked() {
+            ui.close_menu();
+            let _ = store.save();
+            let _ = store.git_commit();
+        }
+        if let Some(error) = &store.git_error {
+            ui.colored_label(ui.visuals().error_fg_color, format!("Last commit failed: {}", error));
+        }
+        ui.separator();
+        let mut cfg = store.config.git.clone();
+        //the repository holds the workspace as a sub dir, it's only committed by hand
+        let is_own = store.note_space.git().is_some_and(|repo| repo.is_own());
+        ui.add_enabled(is_own, Checkbox::new(&mut cfg.auto_commit, "Auto commit"))
+            .on_disabled_hover_text("The workspace is a sub dir of another repository, commit it by hand");
+        ui.add_enabled_ui(cfg.auto_commit && is_own, |ui| {
+            ui.radio_value(&mut cfg.commit_on_save, true, "On save");
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut cfg.commit_on_save, false, "Every");
+                ui.add(DragValue::new(&mut cfg.interval_mins).range(1..=1440).suffix(" min"));
+            });
+        });
+        if cfg != store.config.git {
+            store.config.git = cfg;
+            store.config_save();
+        }
+    }
+
+    fn workspace_menus(store: &mut Store, ui: &mut Ui) {
+        Self::set_ui_button_font(ui);
+
+        let current = store.current_workspace();
+        let mut need_switch = None;
+        for dir in &store.workspaces.recent {
+            let button = Button::new(Self::button_galley(ui, dir, None)).selected(*dir == current);
+            if button.ui(ui).clicked() {
+                ui.close_menu();
+                need_switch = Some(dir.clone());
+            }
+        }
+        if let Some(dir) = need_switch {
+            store.switch_workspace(&dir);
+        }
+
+        ui.separator();
+        if ui.button(Self::button_galley(ui, "Open...", None)).clicked() {
+            ui.close_menu();
+            store.workspace_window.active(&current);
+        }
+    }
+
     fn set_ui_button_font(ui: &mut Ui) {
         let mut font_id = FontId::default();
         //font_id.size = 16.0;
@@ -153,8 +221,85 @@ impl ToolBar<'_> {
                 Self::font_size_menus(store, ui)
             })
             .response.on_hover_text("Font size");
+
+        //workspace menu
+        let current = store.current_workspace();
+        let workspace_name = current.rsplit('/').next().unwrap_or_default().to_string();
+        ui.menu_button(Self::button_galley(ui, &workspace_name, None),
+            |ui| {
+                Self::workspace_menus(store, ui)
+            })
+            .response.on_hover_text(format!("Workspace: {}", current));
     
 
+        //periodic notes
+        let r = Button::new("today").rounding(3.0).ui(ui).on_hover_text("Daily note of today (Alt+T), right click for more");
+        if r.clicked() {
+            let _ = store.open_periodic_today(Period::Daily);
+        }
+        r.context_menu(|ui| {
+            if ui.button("This week (Alt+W)").clicked() {
+                ui.close_menu();
+                let _ = store.open_periodic_today(Period::Weekly);
+            }
+            if ui.button("This month (Alt+M)").clicked() {
+                ui.close_menu();
+                let _ = store.open_periodic_today(Period::Monthly);
+            }
+        });
+        if store.is_cur_periodic() {
+            if ui.small_button("<").on_hover_text("Previous (Alt+PageUp)").clicked() {
+                let _ = store.open_periodic_sibling(-1);
+            }
+            if ui.small_button(">").on_hover_text("Next (Alt+PageDown)").clicked() {
+                let _ = store.open_periodic_sibling(1);
+            }
+        }
+
+        //backlinks button
+        let button = Button::new("backlinks").selected(store.config.show_backlinks).rounding(3.0);
+        if button.ui(ui).on_hover_text("Notes link to current note").clicked() {
+            store.config_switch_show_backlinks();
+        }
+        //mentions button
+        let button = Button::new("mentions").selected(store.config.show_mentions).rounding(3.0);
+        if button.ui(ui).on_hover_text("Unlinked mentions of current note").clicked() {
+            store.config_switch_show_mentions();
+        }
+        //graph button
+        let button = Button::new("graph").selected(store.config.show_graph).rounding(3.0);
+        if button.ui(ui).on_hover_text("Graph of note links").clicked() {
+            store.config_switch_show_graph();
+        }
+        //tags button
+        let button = Button::new("tags").selected(store.config.show_tags).rounding(3.0);
+        if button.ui(ui).on_hover_text("Tags of notes").clicked() {
+            store.config_switch_show_tags();
+        }
+        //history button
+        let button = Button::new("history").rounding(3.0);
+        if button.ui(ui).on_hover_text("Versions of current note").clicked() {
+            store.history_window_active();
+        }
+        //git button
+        let hover = if store.note_space.git().is_some() {
+            "Log and blame of current note, right click for more"
+        } else {
+            "Not a git repository, right click to init"
+        };
+        let r = Button::new("git").rounding(3.0).ui(ui).on_hover_text(hover);
+        if r.clicked() {
+            store.git_window_active();
+        }
+        r.context_menu(|ui| {
+            Self::git_menus(store, ui);
+        });
+        //trash button
+        let button = Button::new("trash").selected(store.config.show_trash).rounding(3.0);
+        if button.ui(ui).on_hover_text("Deleted notes").clicked() {
+            store.config_switch_show_trash();
+        }
+
         if !store.config.fixed_files.is_empty() {
             ui.separator();
         }
@@ -248,7 +393,22 @@ impl ToolBar<'_> {
         let weak_bg_fill = ui.visuals().widgets.inactive.weak_bg_fill;
         ui.visuals_mut().widgets.inactive.weak_bg_fill = Color32::TRANSPARENT;
 
-        let names:Vec<&str> = path.split('/').collect();
+        //note names may contain '/' of folder, so get them from link parents
+        //a note with several parents shows the path chosen by user
+        let mut link_paths = vec![];
+        let names:Vec<String> = if let Some(note) = store.note_space.get_current_note() {
+            link_paths = store.note_space.get_link_paths(&note);
+            let link_path = link_paths.iter()
+                .find(|p| **p == store.tool_bar_info.link_path)
+                .or(link_paths.first())
+                .cloned()
+                .unwrap_or_else(|| vec![note.clone()]);
+            let mut names = vec![".".to_string()];
+            names.extend(link_path);
+            names
+        } else {
+            path.split('/').map(|name| name.to_string()).collect()
+        };
         for (i, name) in names.iter().enumerate() {
             //root
             if name == &"." {} 
@@ -272,11 +432,25 @@ impl ToolBar<'_> {
                 ui.menu_button(
                     Self::button_galley(ui, ">", None), 
                     |ui| {
-                        Self::pop_dir_menus(store, ui, *name)
+                        Self::pop_dir_menus(store, ui, name)
                     });
             }
         }
 
+        //all paths of the note, pick one to show
+        if link_paths.len() > 1 {
+            let text = format!("  {} paths", link_paths.len());
+            ui.menu_button(Self::button_galley(ui, &text, Some(ui.visuals().weak_text_color())), |ui| {
+                for link_path in &link_paths {
+                    let selected = names[1..] == link_path[..];
+                    if ui.selectable_label(selected, link_path.join(" > ")).clicked() {
+                        ui.close_menu();
+                        store.tool_bar_info.link_path = link_path.clone();
+                    }
+                }
+            }).response.on_hover_text("The note is linked from several parents");
+        }
+
         //restore weak_bg_fill
         ui.visuals_mut().widgets.inactive.weak_bg_fill = weak_bg_fill;
 
@@ -0,0 +1,252 @@
+use serde::{Serialize, Deserialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use chrono::{DateTime, Local};
+use eframe::egui::{self, ScrollArea, Window};
+use crate::medit::{Command, IconName};
+use crate::ToolBar;
+
+/// dir of deleted notes in the work dir, it's hidden from the index tree
+pub const TRASH_DIR: &str = ".trash";
+const TRASH_FILE: &str = "trash.json";
+
+/// a link of parent which is removed when the note is deleted
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ParentLink {
+    pub parent: String,
+    pub line: usize,        //line of the link in parent text
+    #[serde(default)]
+    pub removed: Vec<(usize, String)>,  //(offset, text) of the removed links, ascending
+}
+
+impl ParentLink {
+    /// parent text with the removed links inserted back
+    /// the old records without removed texts get a line of [[name]]
+    pub fn restore(&self, text: &str, name: &str) -> String {
+        if self.removed.is_empty() {
+            let mut lines: Vec<&str> = text.split('\n').collect();
+            let new_link = format!("[[{}]]", name);
+            lines.insert(self.line.min(lines.len()), &new_link);
+            return lines.join("\n");
+        }
+        let mut text = text.to_string();
+        for (offset, removed) in &self.removed {
+            //the parent may be changed after deleted
+            let mut at = (*offset).min(text.len());
+            while !text.is_char_boundary(at) {
+                at -= 1;
+            }
+            text.insert_str(at, removed);
+        }
+        text
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrashItem {
+    pub id: String,         //file name in trash dir
+    pub name: String,       //note name before deleted
+    pub deleted: u64,       //unix time in secs
+    pub parents: Vec<ParentLink>,
+}
+
+impl TrashItem {
+    pub fn deleted_time(&self) -> String {
+        DateTime::from_timestamp(self.deleted as i64, 0)
+            .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// deleted notes are moved to ".trash" with the records in "trash.json"
+pub struct Trash {
+    dir: PathBuf,
+    items: Vec<TrashItem>,
+}
+
+impl Trash {
+    pub fn load(work_dir: &Path) -> Self {
+        let dir = work_dir.join(TRASH_DIR);
+        let items = std::fs::read_to_string(dir.join(TRASH_FILE)).ok()
+            .and_then(|json_str| serde_json::from_str::<Vec<TrashItem>>(&json_str).ok())
+            .unwrap_or_default();
+        Self { dir, items }
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let json_str = serde_json::to_string_pretty(&self.items)?;
+        std::fs::write(self.dir.join(TRASH_FILE), json_str)
+    }
+
+    pub fn items(&self) -> &[TrashItem] {
+        &self.items
+    }
+
+    fn item_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.md", id))
+    }
+
+    /// move the file of note to trash
+    pub fn put(&mut self, path: &Path, name: &str, parents: Vec<ParentLink>) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let mut id = now.as_millis().to_string();
+        let mut i = 1;
+        while self.item_path(&id).exists() {
+            id = format!("{}_{}", now.as_millis(), i);
+            i += 1;
+        }
+        //rename fails between devices, copy it then
+        let to = self.item_path(&id);
+        if std::fs::rename(path, &to).is_err() {
+            std::fs::copy(path, &to)?;
+            std::fs::remove_file(path)?;
+        }
+        self.items.push(TrashItem {
+            id,
+            name: name.to_string(),
+            deleted: now.as_secs(),
+            parents,
+        });
+        self.save()
+    }
+
+    /// move the file back to path, return the record
+    pub fn restore(&mut self, id: &str, path: &Path) -> std::io::Result<TrashItem> {
+        let Some(i) = self.items.iter().position(|item| item.id == id) else {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, id));
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::rename(self.item_path(id), path)?;
+        let item = self.items.remove(i);
+        self.save()?;
+        Ok(item)
+    }
+
+    /// delete it permanently
+    pub fn purge(&mut self, id: &str) -> std::io::Result<()> {
+        let _ = std::fs::remove_file(self.item_path(id));
+        self.items.retain(|item| item.id != id);
+        self.save()
+    }
+
+    pub fn purge_all(&mut self) -> std::io::Result<()> {
+        for item in std::mem::take(&mut self.items) {
+            let _ = std::fs::remove_file(self.item_path(&item.id));
+        }
+        self.save()
+    }
+}
+
+/// list of deleted notes, restore or purge them
+pub struct TrashPanel {
+    purge_all_confirm: bool,
+}
+
+impl TrashPanel {
+    pub fn new() -> Self {
+        Self {
+            purge_all_confirm: false,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, trash: &Trash, is_show: &mut bool) -> Option<Command> {
+        let mut cmd = None;
+        if !*is_show {
+            return cmd;
+        }
+
+        let mut open = true;
+        Window::new("trash")
+            .default_size([300.0, 360.0])
+            .title_bar(false)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ToolBar::tool_icon_button(ui, IconName::icon_close, false, false, "Close").clicked() {
+                        *is_show = false;
+                    }
+                    ui.label(format!("Trash: {}", trash.items().len()));
+                    if !trash.items().is_empty() {
+                        if self.purge_all_confirm {
+                            if ui.button("Confirm").clicked() {
+                                cmd = Some(Command::PurgeTrash(None));
+                                self.purge_all_confirm = false;
+                            }
+                            if ui.button("Cancel").clicked() {
+                                self.purge_all_confirm = false;
+                            }
+                        } else if ui.button("Empty").on_hover_text("Delete all permanently").clicked() {
+                            self.purge_all_confirm = true;
+                        }
+                    }
+                });
+                ui.separator();
+
+                ScrollArea::both().auto_shrink(false).show(ui, |ui| {
+                    //the latest first
+                    for item in trash.items().iter().rev() {
+                        ui.horizontal(|ui| {
+                            let parents: Vec<&str> = item.parents.iter().map(|p| p.parent.as_str()).collect();
+                            let hover = if parents.is_empty() {
+                                "No parent".to_string()
+                            } else {
+                                format!("Parents: {}", parents.join(", "))
+                            };
+                            ui.label(&item.name).on_hover_text(hover);
+                            ui.weak(item.deleted_time());
+                            if ui.small_button("restore").on_hover_text("Restore the note and its links in parents").clicked() {
+                                cmd = Some(Command::RestoreTrash(item.id.clone()));
+                            }
+                            if ui.small_button("purge").on_hover_text("Delete permanently").clicked() {
+                                cmd = Some(Command::PurgeTrash(Some(item.id.clone())));
+                            }
+                        });
+                    }
+                    if trash.items().is_empty() {
+                        ui.weak("Trash is empty");
+                    }
+                });
+            });
+        cmd
+    }
+}
+
+#[test]
+pub fn test_trash() {
+    let dir = std::env::temp_dir().join(format!("egscribe_trash_{}", std::process::id()));
+    let _ = std::fs::create_dir_all(&dir);
+    let file = dir.join("a.md");
+    std::fs::write(&file, "hello").unwrap();
+
+    let mut trash = Trash::load(&dir);
+    let parents = vec![ParentLink { parent: "home".to_string(), line: 2, removed: vec![] }];
+    trash.put(&file, "a", parents.clone()).unwrap();
+    assert!(!file.exists());
+
+    //records are saved
+    let mut trash = Trash::load(&dir);
+    assert_eq!(trash.items().len(), 1);
+    let id = trash.items()[0].id.clone();
+    let item = trash.restore(&id, &file).unwrap();
+    assert_eq!(item.parents, parents);
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "hello");
+    assert!(trash.items().is_empty());
+
+    trash.put(&file, "a", vec![]).unwrap();
+    trash.purge_all().unwrap();
+    assert!(Trash::load(&dir).items().is_empty());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+pub fn test_restore_parent_link() {
+    let org = "x\n[[a|alias]]\ny [[id:1#Sec]] [[b]]\n";
+    let (text, removed) = crate::medit::MarkDownImpl::remove_links(org, &["a".to_string(), "id:1".to_string()]);
+    let link = ParentLink { parent: "p".to_string(), line: 1, removed };
+    assert_eq!(link.restore(&text, "a"), org);
+    let old = ParentLink { parent: "p".to_string(), line: 1, removed: vec![] };
+    assert_eq!(old.restore("x\ny", "a"), "x\n[[a]]\ny");
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use eframe::egui::{self, Color32, Order, RichText, ScrollArea, Ui, Window};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use similar::{ChangeTag, TextDiff};
+use crate::history::HISTORY_DIR;
+use crate::space::CurFile;
+use crate::trash::TRASH_DIR;
+
+/// dirs of work dir which are not notes, changes in them are ignored
+const IGNORED_DIRS: [&str; 3] = [".git", HISTORY_DIR, TRASH_DIR];
+
+/// watch the work dir and dirs of opened files, changed paths are received in update loop
+pub struct FileWatcher {
+    watcher: Option<RecommendedWatcher>,
+    receiver: Receiver<PathBuf>,
+    dir_arg: Option<PathBuf>,       //work dir as given, it's checked every frame without canonicalize
+    work_dir: Option<PathBuf>,      //canonical
+    files: Vec<String>,
+    file_dirs: Vec<PathBuf>,
+}
+
+impl FileWatcher {
+    pub fn new(egui_ctx: &egui::Context) -> Self {
+        let (sender, receiver) = channel();
+        let egui_ctx = egui_ctx.clone();
+        let watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                if let EventKind::Access(_) = event.kind {
+                    return;
+                }
+                for path in event.paths {
+                    let _ = sender.send(path);
+                }
+                egui_ctx.request_repaint();
+            }
+        });
+        Self {
+            watcher: watcher.ok(),
+            receiver,
+            dir_arg: None,
+            work_dir: None,
+            files: vec![],
+            file_dirs: vec![],
+        }
+    }
+
+    fn abs_path(path: &Path) -> PathBuf {
+        std::fs::canonicalize(path).unwrap_or(path.to_path_buf())
+    }
+
+    /// watch the work dir recursively, the old one is unwatched
+    pub fn watch_work_dir(&mut self, dir: &Path) {
+        if self.dir_arg.as_deref() == Some(dir) {
+            return;
+        }
+        self.dir_arg = Some(dir.to_path_buf());
+        let dir = Self::abs_path(dir);
+        if self.work_dir.as_ref() == Some(&dir) {
+            return;
+        }
+        if let Some(watcher) = &mut self.watcher {
+            if let Some(old) = &self.work_dir {
+                let _ = watcher.unwatch(old);
+            }
+            let _ = watcher.watch(&dir, RecursiveMode::Recursive);
+        }
+        self.work_dir = Some(dir);
+    }
+
+    /// watch dirs of opened files, the file may be replaced by editors, so its dir is watched
+    pub fn watch_files(&mut self, files: &[String]) {
+        if files == self.files {
+            return;
+        }
+        self.files = files.to_vec();
+        let mut dirs: Vec<PathBuf> = files.iter()
+            .filter_map(|f| Self::abs_path(Path::new(f)).parent().map(|p| p.to_path_buf()))
+            .filter(|d| !self.work_dir.as_ref().is_some_and(|w| d.starts_with(w)))
+            .collect();
+        dirs.sort();
+        dirs.dedup();
+        if dirs == self.file_dirs {
+            return;
+        }
+        if let Some(watcher) = &mut self.watcher {
+            for dir in &self.file_dirs {
+                if !dirs.contains(dir) {
+                    let _ = watcher.unwatch(dir);
+                }
+            }
+            for dir in &dirs {
+                if !self.file_dirs.contains(dir) {
+                    let _ = watcher.watch(dir, RecursiveMode::NonRecursive);
+                }
+            }
+        }
+        self.file_dirs = dirs;
+    }
+
+    /// canonical work dir being watched
+    pub fn work_dir(&self) -> Option<&Path> {
+        self.work_dir.as_deref()
+    }
+
+    /// changed paths since last poll
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut paths = vec![];
+        while let Ok(path) = self.receiver.try_recv() {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+        paths
+    }
+
+    /// a note or a folder of notes in work dir is changed
+    pub fn is_note_change(work_dir: &Path, path: &Path) -> bool {
+        let Ok(rel) = path.strip_prefix(work_dir) else {
+            return false;
+        };
+        let ignored = rel.components().any(|c| IGNORED_DIRS.iter().any(|d| c.as_os_str() == *d));
+        !ignored && path.extension().map_or(true, |e| e == "md")
+    }
+
+    pub fn is_same_path(a: &Path, b: &Path) -> bool {
+        a == b || Self::abs_path(a) == Self::abs_path(b)
+    }
+}
+
+struct Conflict {
+    curfile: CurFile,
+    mine: String,
+    theirs: String,
+}
+
+pub enum ConflictAction {
+    KeepMine,
+    TakeTheirs(String),
+}
+
+/// ask user when a changed buffer is also changed on disk
+pub struct ConflictWin {
+    conflicts: Vec<Conflict>,
+    kept: HashMap<CurFile, String>,     //disk text which user chose to ignore
+    show_diff: bool,
+}
+
+impl ConflictWin {
+    pub fn default() -> Self {
+        Self {
+            conflicts: vec![],
+            kept: HashMap::new(),
+            show_diff: false,
+        }
+    }
+
+    pub fn push(&mut self, curfile: CurFile, mine: String, theirs: String) {
+        if self.kept.get(&curfile) == Some(&theirs) {
+            return;
+        }
+        self.conflicts.retain(|c| c.curfile != curfile);
+        self.conflicts.push(Conflict { curfile, mine, theirs });
+    }
+
+    pub fn show_diff_lines(ui: &mut Ui, mine: &str, theirs: &str) {
+        let diff = TextDiff::from_lines(mine, theirs);
+        for change in diff.iter_all_changes() {
+            let (sign, color) = match change.tag() {
+                ChangeTag::Delete => ("-", Some(Color32::from_rgb(220, 80, 80))),
+                ChangeTag::Insert => ("+", Some(Color32::from_rgb(80, 180, 80))),
+                ChangeTag::Equal => (" ", None),
+            };
+            let text = format!("{} {}", sign, change.value().trim_end_matches('\n'));
+            let mut text = RichText::new(text).monospace();
+            if let Some(color) = color {
+                text = text.color(color);
+            }
+            ui.label(text);
+        }
+    }
+
+    //return the choice of user
+    pub fn show(&mut self, ui: &mut Ui) -> Option<(CurFile, ConflictAction)> {
+        let mut action = None;
+        let conflict = self.conflicts.first()?;
+        let egui_ctx = ui.ctx();
+        Window::new("file changed on disk")
+            .order(Order::TOP)
+            .collapsible(false)
+            .show(egui_ctx, |ui| {
+                ui.label(format!("\"{}\" has been changed on disk, and it has unsaved changes.", conflict.curfile.name()));
+                ui.horizontal(|ui| {
+                    if ui.button("keep mine").clicked() {
+                        action = Some(ConflictAction::KeepMine);
+                    }
+                    if ui.button("take theirs").clicked() {
+                        action = Some(ConflictAction::TakeTheirs(conflict.theirs.clone()));
+                    }
+                    let diff_text = if self.show_diff {"hide diff"} else {"diff"};
+                    if ui.button(diff_text).clicked() {
+                        self.show_diff = !self.show_diff;
+                    }
+                });
+                if self.show_diff {
+                    ui.separator();
+                    ScrollArea::both().max_height(360.0).auto_shrink([false, true]).show(ui, |ui| {
+                        Self::show_diff_lines(ui, &conflict.mine, &conflict.theirs);
+                    });
+                }
+            });
+
+        let action = action?;
+        let conflict = self.conflicts.remove(0);
+        self.show_diff = false;
+        if let ConflictAction::KeepMine = action {
+            self.kept.insert(conflict.curfile.clone(), conflict.theirs);
+        } else {
+            self.kept.remove(&conflict.curfile);
+        }
+        Some((conflict.curfile, action))
+    }
+}
+
+#[test]
+pub fn test_file_watcher() {
+    let dir = std::env::temp_dir().join(format!("egscribe_watch_{}", std::process::id()));
+    let _ = std::fs::create_dir_all(&dir);
+    let mut watcher = FileWatcher::new(&egui::Context::default());
+    watcher.watch_work_dir(&dir);
+    assert_eq!(watcher.work_dir(), std::fs::canonicalize(&dir).ok().as_deref());
+    let file = dir.join("a.md");
+    std::fs::write(&file, "hello").unwrap();
+    //events come from another thread
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    let mut found = false;
+    while !found && std::time::Instant::now() < deadline {
+        found = watcher.poll().iter().any(|p| FileWatcher::is_same_path(p, &file));
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+    assert!(found);
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let work_dir = Path::new("/notes");
+    assert!(FileWatcher::is_note_change(work_dir, Path::new("/notes/f/a.md")));
+    assert!(FileWatcher::is_note_change(work_dir, Path::new("/notes/f")));
+    assert!(!FileWatcher::is_note_change(work_dir, Path::new("/notes/.git/index")));
+    assert!(!FileWatcher::is_note_change(work_dir, Path::new("/notes/.history/a/1.md")));
+    assert!(!FileWatcher::is_note_change(work_dir, Path::new("/notes/.trash/a.md")));
+    assert!(!FileWatcher::is_note_change(work_dir, Path::new("/other/a.md")));
+}
@@ -0,0 +1,198 @@
+use serde::{Serialize, Deserialize};
+use std::path::PathBuf;
+use eframe::egui::{Order, Rect, Ui, Vec2, Window};
+
+/// env var to choose the workspace
+pub const WORKSPACE_ENV: &str = "EGSCRIBE_WORKSPACE";
+const MAX_RECENT: usize = 10;
+
+/// app settings, saved in $XDG_CONFIG_HOME/egscribe/settings.json
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Workspaces {
+    #[serde(default)]
+    pub workspace: Option<String>,  //default workspace set by user
+    #[serde(default)]
+    pub recent: Vec<String>,        //the first is the last opened
+}
+
+impl Workspaces {
+    fn config_dir() -> Option<PathBuf> {
+        if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME").filter(|d| !d.is_empty()) {
+            return Some(PathBuf::from(dir));
+        }
+        if let Some(dir) = std::env::var_os("APPDATA").filter(|d| !d.is_empty()) {
+            return Some(PathBuf::from(dir));
+        }
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+    }
+
+    fn data_dir() -> Option<PathBuf> {
+        if let Some(dir) = std::env::var_os("XDG_DATA_HOME").filter(|d| !d.is_empty()) {
+            return Some(PathBuf::from(dir));
+        }
+        if let Some(dir) = std::env::var_os("APPDATA").filter(|d| !d.is_empty()) {
+            return Some(PathBuf::from(dir));
+        }
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+    }
+
+    fn settings_file() -> Option<PathBuf> {
+        Self::config_dir().map(|dir| dir.join("egscribe").join("settings.json"))
+    }
+
+    /// the old place of workspace: <exe dir>/note
+    fn exe_note_dir() -> Option<PathBuf> {
+        let exe_path = std::env::current_exe().ok()?;
+        exe_path.parent().map(|p| p.join("note"))
+    }
+
+    pub fn load() -> Self {
+        if let Some(file) = Self::settings_file() {
+            if let Ok(json_str) = std::fs::read_to_string(&file) {
+                if let Ok(settings) = serde_json::from_str::<Workspaces>(&json_str) {
+                    return settings;
+                }
+            }
+        }
+        Self::default()
+    }
+
+    pub fn save(&self) {
+        if let Some(file) = Self::settings_file() {
+            if let Some(dir) = file.parent() {
+                let _ = std::fs::create_dir_all(dir);
+            }
+            let json_str = serde_json::to_string_pretty(self).unwrap();
+            let _ = std::fs::write(&file, json_str);
+        }
+    }
+
+    /// choose workspace by order:
+    /// cli flag, env var, default in settings, last opened, <exe dir>/note, $XDG_DATA_HOME/egscribe/note
+    pub fn resolve(&self, cli: Option<String>) -> PathBuf {
+        if let Some(dir) = cli.filter(|d| !d.is_empty()) {
+            return PathBuf::from(dir);
+        }
+        if let Ok(dir) = std::env::var(WORKSPACE_ENV) {
+            if !dir.is_empty() {
+                return PathBuf::from(dir);
+            }
+        }
+        if let Some(dir) = &self.workspace {
+            return PathBuf::from(dir);
+        }
+        if let Some(dir) = self.recent.first() {
+            return PathBuf::from(dir);
+        }
+        if let Some(dir) = Self::exe_note_dir().filter(|d| d.is_dir()) {
+            return dir;
+        }
+        if let Some(dir) = Self::data_dir() {
+            return dir.join("egscribe").join("note");
+        }
+        PathBuf::from("./note")
+    }
+
+    pub fn add_recent(&mut self, dir: &PathBuf) {
+        let dir = Self::abs_path(dir);
+        self.recent.retain(|d| *d != dir);
+        self.recent.insert(0, dir);
+        self.recent.truncate(MAX_RECENT);
+    }
+
+    pub fn abs_path(dir: &PathBuf) -> String {
+        let dir = std::fs::canonicalize(dir).unwrap_or(dir.clone());
+        dir.to_string_lossy().replace("\\", "/")
+    }
+}
+
+/// window to input the dir of workspace
+pub struct WorkspaceWin {
+    is_show: bool,
+    need_focus: bool,
+    path: String,
+}
+
+impl WorkspaceWin {
+    pub fn default() -> Self {
+        Self {
+            is_show: false,
+            need_focus: false,
+            path: String::new(),
+        }
+    }
+
+    pub fn active(&mut self, path: &str) {
+        self.is_show = true;
+        self.need_focus = true;
+        self.path = path.to_string();
+    }
+
+    //return the dir when click open-button
+    pub fn show(&mut self, ui: &mut Ui) -> Option<String> {
+        if !self.is_show {
+            return None;
+        }
+
+        let size = Vec2::new(360.0, 30.0);
+        let mut rect = Rect::from_min_size(ui.cursor().left_top(), size);
+        if let Some(pointer_pos) = ui.ctx().pointer_interact_pos() {
+            rect.min = pointer_pos;
+        }
+        let mut need_open = false;
+        let egui_ctx = ui.ctx();
+        Window::new("open workspace")
+            .default_rect(rect)
+            .open(&mut self.is_show)
+            .resizable([false, false])
+            .order(Order::TOP)
+            .show(egui_ctx, |ui| {
+                ui.horizontal(|ui|{
+                    let r = ui.text_edit_singleline(&mut self.path);
+                    if self.need_focus {
+                        self.need_focus = false;
+                        r.request_focus();
+                    }
+                    if ui.button("open").clicked() {
+                        need_open = true;
+                    }
+                });
+            });
+
+        if need_open && !self.path.trim().is_empty() {
+            self.is_show = false;
+            return Some(self.path.trim().to_string());
+        }
+        None
+    }
+}
+
+/// parse cli args: [--workspace <dir>] [file]
+pub fn parse_args(args: &[String]) -> (Option<String>, String) {
+    let mut workspace = None;
+    let mut file = String::new();
+    let mut i = 1;
+    while i < args.len() {
+        let arg = &args[i];
+        if arg == "--workspace" || arg == "-w" {
+            workspace = args.get(i + 1).cloned();
+            i += 1;
+        } else if let Some(dir) = arg.strip_prefix("--workspace=") {
+            workspace = Some(dir.to_string());
+        } else if file.is_empty() {
+            file = arg.clone();
+        }
+        i += 1;
+    }
+    (workspace, file)
+}
+
+#[test]
+pub fn test_parse_args() {
+    let args: Vec<String> = ["egscribe", "-w", "/tmp/note", "a.rs"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(parse_args(&args), (Some("/tmp/note".to_string()), "a.rs".to_string()));
+    let args: Vec<String> = ["egscribe", "--workspace=/tmp/x"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(parse_args(&args), (Some("/tmp/x".to_string()), String::new()));
+    let args: Vec<String> = ["egscribe", "b.md"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(parse_args(&args), (None, "b.md".to_string()));
+}